@@ -0,0 +1,434 @@
+//! Accessor traits and views shared by an injectable struct and every struct injecting it.
+//!
+//! `#[injectable(accessors)]` generates a `Has<Name>` trait with a getter and a `_mut` accessor
+//! for each field, and `#[injectable(views)]` generates `<Name>Ref<'view>` and `<Name>Mut<'view>`
+//! structs borrowing each field, with `as_<name>_ref` and `as_<name>_mut` methods returning them.
+//! Both are provided by the struct itself, and its callback macro carries them as hidden
+//! `#[__injectables_fields(...)]` attributes (see [`FieldSet`]), from which `#[inject_fields]`
+//! provides them for the target as well.
+//!
+//! A target that is injectable in turn passes the field sets it provides on through its own
+//! callback, so a struct injecting it provides those of transitive sources too.
+
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+
+use crate::{
+  generics::GenericSubstitution,
+  types::{cfg_attrs, snake_case, FieldSet, FieldSetField, FieldSetKind, InjectConfig, InjectSource, ModuleInfo},
+  visibility::is_visible_as,
+};
+
+/// Name of the hidden attribute carrying a field set through a callback macro.
+const FIELD_SET_ATTR:&str = "__injectables_fields";
+
+/// Checks whether an attribute carries a field set.
+pub fn is_field_set_attr(attr:&syn::Attribute) -> bool { attr.path().is_ident(FIELD_SET_ATTR) }
+
+/// Wraps a field set in the hidden attribute carrying it.
+pub fn field_set_attr(set:&FieldSet) -> syn::Attribute {
+  let ident = syn::Ident::new(FIELD_SET_ATTR, proc_macro2::Span::call_site());
+  syn::parse_quote!(#[#ident(#set)])
+}
+
+/// Returns the name of the accessor trait of an injectable struct.
+fn trait_ident(ident:&syn::Ident) -> syn::Ident { format_ident!("Has{}", ident) }
+
+/// Returns the names of the borrowed and mutable views of an injectable struct.
+fn view_idents(ident:&syn::Ident) -> (syn::Ident, syn::Ident) {
+  (format_ident!("{}Ref", ident), format_ident!("{}Mut", ident))
+}
+
+/// Generates the accessor trait of an injectable struct, and its implementation for the struct.
+///
+/// The trait has the struct's generic parameters and visibility, and accessors for the fields at
+/// least as visible as the struct: an accessor for any other field would hand it out to modules
+/// that may not access it. The accessors of a field declared under `#[cfg(...)]` carry its cfg
+/// attributes, in the trait and in each implementation.
+///
+/// # Arguments
+///
+/// * `input` - The injectable struct
+///
+/// # Returns
+///
+/// The trait definition and its implementation
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // `#[injectable(accessors)] pub struct Auditable { pub created_by: String }` generates:
+/// pub trait HasAuditable {
+///   fn created_by(&self) -> &String;
+///   fn created_by_mut(&mut self) -> &mut String;
+/// }
+/// ```
+pub fn accessor_trait(input:&syn::DeriveInput) -> TokenStream {
+  let ident = &input.ident;
+  let vis = &input.vis;
+  let trait_ident = trait_ident(ident);
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  let fields:Vec<_> = accessible_fields(input).collect();
+  let cfgs:Vec<_> = fields.iter().map(|field| cfg_attrs(&field.attrs)).collect();
+  let getters:Vec<_> = fields.iter().map(|field| &field.ident).collect();
+  let setters:Vec<_> = fields
+    .iter()
+    .map(|field| format_ident!("{}_mut", field.ident.as_ref().unwrap()))
+    .collect();
+  let types:Vec<_> = fields.iter().map(|field| &field.ty).collect();
+  let doc = format!(
+    "Accessors for the fields of [`{}`], implemented by every struct injecting them.",
+    ident
+  );
+
+  quote! {
+    #[doc = #doc]
+    #vis trait #trait_ident #impl_generics #where_clause {
+      #(
+        #(#cfgs)*
+        fn #getters(&self) -> &#types;
+        #(#cfgs)*
+        fn #setters(&mut self) -> &mut #types;
+      )*
+    }
+
+    impl #impl_generics #trait_ident #ty_generics for #ident #ty_generics #where_clause {
+      #(
+        #(#cfgs)*
+        fn #getters(&self) -> &#types {
+          &self.#getters
+        }
+
+        #(#cfgs)*
+        fn #setters(&mut self) -> &mut #types {
+          &mut self.#getters
+        }
+      )*
+    }
+  }
+}
+
+/// Generates the borrowed and mutable views of an injectable struct, and the methods returning
+/// them from the struct itself.
+///
+/// The views have the struct's generic parameters, preceded by the `'view` lifetime of the
+/// borrow, and each of their fields has the visibility and the cfg attributes of the field it
+/// borrows.
+///
+/// # Arguments
+///
+/// * `input` - The injectable struct
+///
+/// # Returns
+///
+/// The view definitions and the methods returning them
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // `#[injectable(views)] pub struct Base { pub id: u64 }` generates:
+/// pub struct BaseRef<'view> {
+///   pub id: &'view u64,
+/// }
+/// pub struct BaseMut<'view> {
+///   pub id: &'view mut u64,
+/// }
+/// ```
+pub fn view_structs(input:&syn::DeriveInput) -> TokenStream {
+  let ident = &input.ident;
+  let vis = &input.vis;
+  let (ref_ident, mut_ident) = view_idents(ident);
+
+  let mut view_generics = input.generics.clone();
+  view_generics.params.insert(0, syn::parse_quote!('view));
+  let (view_impl_generics, _, view_where_clause) = view_generics.split_for_impl();
+
+  let cfgs:Vec<_> = named_fields(input).map(|field| cfg_attrs(&field.attrs)).collect();
+  let field_vis:Vec<_> = named_fields(input).map(|field| &field.vis).collect();
+  let names:Vec<_> = named_fields(input).map(|field| &field.ident).collect();
+  let types:Vec<_> = named_fields(input).map(|field| &field.ty).collect();
+  let ref_doc = format!("Borrowed view of the fields of [`{}`].", ident);
+  let mut_doc = format!("Mutable view of the fields of [`{}`].", ident);
+
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+  let methods = view_methods(&own_field_set(input, FieldSetKind::View), vis);
+
+  quote! {
+    #[doc = #ref_doc]
+    #vis struct #ref_ident #view_impl_generics #view_where_clause {
+      #(#(#cfgs)* #field_vis #names: &'view #types,)*
+    }
+
+    #[doc = #mut_doc]
+    #vis struct #mut_ident #view_impl_generics #view_where_clause {
+      #(#(#cfgs)* #field_vis #names: &'view mut #types,)*
+    }
+
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #methods
+    }
+  }
+}
+
+/// Describes the accessor trait or the views of an injectable struct, as provided by the struct
+/// itself, for its callback macro.
+///
+/// The path is written relative to the struct's module, as `self::Has<Name>` or `self::<Name>`,
+/// so that consumers rebase it like the field types of the definition. The accessor trait covers
+/// the fields [`accessor_trait`] generates accessors for.
+///
+/// # Arguments
+///
+/// * `definition` - The struct definition published by the callback macro
+/// * `kind` - Whether to describe the accessor trait or the views
+pub fn own_field_set(definition:&syn::DeriveInput, kind:FieldSetKind) -> FieldSet {
+  let ident = match kind {
+    FieldSetKind::Accessors => trait_ident(&definition.ident),
+    FieldSetKind::View => definition.ident.clone(),
+  };
+  let (_, ty_generics, _) = definition.generics.split_for_impl();
+
+  FieldSet {
+    kind,
+    path:syn::parse_quote!(self::#ident #ty_generics),
+    fields:named_fields(definition)
+      .filter(|field| kind == FieldSetKind::View || is_visible_as(&field.vis, &definition.vis))
+      .map(|field| {
+        let name = field.ident.clone().unwrap();
+        FieldSetField {
+          cfgs:cfg_attrs(&field.attrs),
+          ty:field.ty.clone(),
+          place:std::iter::once(name.clone()).collect(),
+          name,
+        }
+      })
+      .collect(),
+  }
+}
+
+/// Provides the accessor traits and views of the sources for the target.
+///
+/// A field set is provided if all of its fields were injected; with `only(...)` or `except(...)`
+/// leaving some out, or the target overriding some, it is skipped. A set carried by several
+/// sources is provided once if they all carry it in the same places, and not at all otherwise.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source struct, in the same order as `config.structs`
+/// * `target` - The target struct, with its fields injected
+/// * `overrides` - Names of the target's own fields replacing injected ones
+///
+/// # Returns
+///
+/// The trait implementations and view methods, and the field sets as provided by the target for
+/// its own callback
+pub fn field_set_impls(
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
+  target:&syn::DeriveInput,
+  overrides:&HashSet<String>,
+) -> (TokenStream, Vec<FieldSet>) {
+  let ident = &target.ident;
+  let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+
+  // A set arriving again with the same fields in the same places is shared, as in a diamond, and
+  // provided once. Sets clashing with another, such as the accessors of a source injected twice
+  // under different names or the views of two instantiations of a source, are not provided at all
+  let mut candidates:Vec<(String, FieldSet)> = Vec::new();
+  let mut ambiguous = HashSet::new();
+  for (injection, source) in config.structs.iter().zip(sources) {
+    for set in &source.field_sets {
+      let Some(set) = project_field_set(injection, source, set, overrides) else {
+        continue;
+      };
+      let key = match set.kind {
+        FieldSetKind::Accessors => format!("accessors {}", set.path.to_token_stream()),
+        FieldSetKind::View => format!("view {}", set.path.segments.last().unwrap().ident),
+      };
+      match candidates.iter().find(|(existing, _)| existing == &key) {
+        Some((_, existing)) if same_field_set(existing, &set) => {}
+        Some(_) => {
+          ambiguous.insert(key);
+        }
+        None => candidates.push((key, set)),
+      }
+    }
+  }
+
+  let mut impls = TokenStream::new();
+  let mut field_sets = Vec::new();
+  for (key, set) in candidates {
+    if ambiguous.contains(&key) {
+      continue;
+    }
+    impls.extend(match set.kind {
+      FieldSetKind::Accessors => {
+        let path = &set.path;
+        let cfgs:Vec<_> = set.fields.iter().map(|field| &field.cfgs).collect();
+        let getters:Vec<_> = set.fields.iter().map(|field| &field.name).collect();
+        let setters:Vec<_> = getters.iter().map(|name| format_ident!("{}_mut", name)).collect();
+        let types:Vec<_> = set.fields.iter().map(|field| &field.ty).collect();
+        let places:Vec<_> = set.fields.iter().map(|field| &field.place).collect();
+        quote! {
+          impl #impl_generics #path for #ident #ty_generics #where_clause {
+            #(
+              #(#cfgs)*
+              fn #getters(&self) -> &#types {
+                &self.#places
+              }
+
+              #(#cfgs)*
+              fn #setters(&mut self) -> &mut #types {
+                &mut self.#places
+              }
+            )*
+          }
+        }
+      }
+      FieldSetKind::View => {
+        let methods = view_methods(&set, &target.vis);
+        quote! {
+          impl #impl_generics #ident #ty_generics #where_clause {
+            #methods
+          }
+        }
+      }
+    });
+
+    field_sets.push(set);
+  }
+
+  (impls, field_sets)
+}
+
+/// Checks whether two field sets carry the same fields in the same places.
+fn same_field_set(a:&FieldSet, b:&FieldSet) -> bool {
+  let describe = |set:&FieldSet| set.to_token_stream().to_string();
+  describe(a) == describe(b)
+}
+
+/// Maps a field set carried by a source onto the target injecting the source.
+///
+/// The places of the fields are renamed or prefixed as the source's fields were, or nested under
+/// the source's field if it is embedded, and the source's generic arguments are substituted into
+/// the path and the field types.
+///
+/// # Returns
+///
+/// The field set as found in the target, or `None` if not all of its fields were injected, or some
+/// were overridden by the target
+fn project_field_set(
+  injection:&InjectSource,
+  source:&ModuleInfo,
+  set:&FieldSet,
+  overrides:&HashSet<String>,
+) -> Option<FieldSet> {
+  let last_segment = injection.path.path.segments.last().unwrap();
+  let substitution = GenericSubstitution::new(&source.generics, &last_segment.arguments);
+  let embedded = injection.embedded_name();
+
+  let mut fields = Vec::new();
+  for field in &set.fields {
+    let mut place = field.place.clone();
+    match &embedded {
+      Some(embedded) => place.insert(0, embedded.clone()),
+      None => {
+        let first = &mut place[0];
+        let name = injection.options.field_name(&first.to_string());
+        if !injection.options.includes(&first.to_string()) || overrides.contains(&name) {
+          return None;
+        }
+        *first = syn::Ident::new(&name, first.span());
+      }
+    }
+
+    fields.push(FieldSetField {
+      cfgs:field.cfgs.clone(),
+      name:field.name.clone(),
+      ty:substitution.apply(&field.ty),
+      place,
+    });
+  }
+
+  let path = match substitution.apply(&syn::Type::Path(syn::TypePath {
+    qself:None,
+    path: set.path.clone(),
+  })) {
+    syn::Type::Path(type_path) => type_path.path,
+    _ => unreachable!("substitution keeps a path a path"),
+  };
+
+  Some(FieldSet {
+    kind:set.kind,
+    path,
+    fields,
+  })
+}
+
+/// Generates the `as_<name>_ref` and `as_<name>_mut` methods returning the views of a field set.
+fn view_methods(set:&FieldSet, vis:&syn::Visibility) -> TokenStream {
+  let last_segment = set.path.segments.last().unwrap();
+  let (ref_ident, mut_ident) = view_idents(&last_segment.ident);
+  let name = snake_case(&last_segment.ident);
+  let as_ref = format_ident!("as_{}_ref", name);
+  let as_mut = format_ident!("as_{}_mut", name);
+
+  // The views take the borrow's lifetime ahead of the struct's own generic arguments
+  let mut arguments = match &last_segment.arguments {
+    syn::PathArguments::AngleBracketed(arguments) => arguments.clone(),
+    _ => syn::parse_quote!(<>),
+  };
+  arguments.args.insert(0, syn::parse_quote!('_));
+
+  let mut ref_path = set.path.clone();
+  let mut mut_path = set.path.clone();
+  for (path, ident) in [(&mut ref_path, ref_ident), (&mut mut_path, mut_ident)] {
+    let segment = path.segments.last_mut().unwrap();
+    segment.ident = ident;
+    segment.arguments = syn::PathArguments::AngleBracketed(arguments.clone());
+  }
+
+  // Struct expressions leave the generic arguments to inference
+  let mut ref_ctor = ref_path.clone();
+  let mut mut_ctor = mut_path.clone();
+  ref_ctor.segments.last_mut().unwrap().arguments = syn::PathArguments::None;
+  mut_ctor.segments.last_mut().unwrap().arguments = syn::PathArguments::None;
+
+  let cfgs:Vec<_> = set.fields.iter().map(|field| &field.cfgs).collect();
+  let names:Vec<_> = set.fields.iter().map(|field| &field.name).collect();
+  let places:Vec<_> = set.fields.iter().map(|field| &field.place).collect();
+  let ref_doc = format!("Borrows the fields of [`{}`].", last_segment.ident);
+  let mut_doc = format!("Mutably borrows the fields of [`{}`].", last_segment.ident);
+
+  quote! {
+    #[doc = #ref_doc]
+    #vis fn #as_ref(&self) -> #ref_path {
+      #ref_ctor { #(#(#cfgs)* #names: &self.#places),* }
+    }
+
+    #[doc = #mut_doc]
+    #vis fn #as_mut(&mut self) -> #mut_path {
+      #mut_ctor { #(#(#cfgs)* #names: &mut self.#places),* }
+    }
+  }
+}
+
+/// Iterates over the named fields of a struct that are at least as visible as the struct.
+fn accessible_fields(input:&syn::DeriveInput) -> impl Iterator<Item = &syn::Field> {
+  named_fields(input).filter(|field| is_visible_as(&field.vis, &input.vis))
+}
+
+/// Iterates over the named fields of a struct.
+fn named_fields(input:&syn::DeriveInput) -> impl Iterator<Item = &syn::Field> {
+  match &input.data {
+    syn::Data::Struct(syn::DataStruct {
+      fields: syn::Fields::Named(fields),
+      ..
+    }) => fields.named.iter(),
+    _ => unreachable!("injectable structs have named fields"),
+  }
+}
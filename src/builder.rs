@@ -0,0 +1,167 @@
+//! Builders for injection targets.
+//!
+//! With the `builder` option, `#[inject_fields]` generates for a target `Document`:
+//! - `DocumentBuilder`, with a setter for each field of the target, its own and injected ones
+//! - `Document::builder()`, returning an empty builder
+//! - `DocumentBuilder::build()`, returning the target or a `DocumentBuilderError` listing every
+//!   field that was not set and has no default
+//!
+//! Defaults are given with `#[inject(default)]` or `#[inject(default = <expr>)]`, on a field of
+//! the target or of a source. A field declared under `#[cfg(...)]` has its builder field, setter
+//! and checks under the same cfg attributes.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::types::{cfg_attrs, FieldOptions};
+
+/// Generates the builder of a target.
+///
+/// # Arguments
+///
+/// * `target` - The target struct, with its fields injected
+/// * `options` - Options of each field of the target, in declaration order
+///
+/// # Returns
+///
+/// The builder, its error type and the `builder()` constructor
+pub fn builder_impls(target:&syn::DeriveInput, options:&[FieldOptions]) -> TokenStream {
+  let ident = &target.ident;
+  let vis = &target.vis;
+  let builder = format_ident!("{}Builder", ident);
+  let error = format_ident!("{}BuilderError", ident);
+  let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+
+  let fields:Vec<_> = match &target.data {
+    syn::Data::Struct(syn::DataStruct {
+      fields: syn::Fields::Named(fields),
+      ..
+    }) => fields.named.iter().collect(),
+    _ => unreachable!("injection targets are structs with named fields"),
+  };
+  let cfgs:Vec<_> = fields.iter().map(|field| cfg_attrs(&field.attrs)).collect();
+  let names:Vec<_> = fields.iter().map(|field| &field.ident).collect();
+  let types:Vec<_> = fields.iter().map(|field| &field.ty).collect();
+
+  // Setters convert their argument, except where that would defeat inference of the argument
+  let params:Vec<_> = types
+    .iter()
+    .map(|ty| {
+      if takes_exact_type(ty, &target.generics) {
+        quote!(#ty)
+      } else {
+        quote!(impl ::core::convert::Into<#ty>)
+      }
+    })
+    .collect();
+
+  // Required fields are reported when missing, the others fall back to their default
+  let (required_cfgs, required):(Vec<_>, Vec<&syn::Ident>) = cfgs
+    .iter()
+    .zip(&names)
+    .zip(options)
+    .filter(|(_, options)| options.default.is_none())
+    .map(|((cfgs, name), _)| (cfgs, name.as_ref().unwrap()))
+    .unzip();
+  let required_names = required.iter().map(|name| name.to_string());
+  let values = names.iter().zip(options).map(|(name, options)| {
+    match &options.default {
+      Some(default) => quote!(self.#name.unwrap_or_else(|| #default)),
+      None => quote!(self.#name.unwrap()),
+    }
+  });
+
+  let setter_docs = names.iter().map(|name| format!("Sets `{}`.", name.as_ref().unwrap()));
+  let builder_doc = format!("Builder for [`{}`].", ident);
+  let error_doc = format!(
+    "Error building a [`{}`] without setting all of its required fields.",
+    ident
+  );
+  let message = format!("missing fields for {}: ", ident);
+
+  quote! {
+    #[doc = #builder_doc]
+    #vis struct #builder #impl_generics #where_clause {
+      #(#(#cfgs)* #names: ::core::option::Option<#types>,)*
+    }
+
+    #[doc = #error_doc]
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    #vis struct #error {
+      /// Names of the required fields that were not set.
+      pub missing: ::std::vec::Vec<&'static str>,
+    }
+
+    impl ::core::fmt::Display for #error {
+      fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "{}{}", #message, self.missing.join(", "))
+      }
+    }
+
+    impl ::std::error::Error for #error {}
+
+    impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+      fn default() -> Self {
+        Self {
+          #(#(#cfgs)* #names: ::core::option::Option::None,)*
+        }
+      }
+    }
+
+    impl #impl_generics #builder #ty_generics #where_clause {
+      #(
+        #[doc = #setter_docs]
+        #(#cfgs)*
+        #vis fn #names(mut self, #names: #params) -> Self {
+          self.#names = ::core::option::Option::Some(#names.into());
+          self
+        }
+      )*
+
+      /// Builds the struct, failing if a required field was not set.
+      #vis fn build(self) -> ::core::result::Result<#ident #ty_generics, #error> {
+        let mut missing = ::std::vec::Vec::new();
+        #(
+          #(#required_cfgs)*
+          if self.#required.is_none() {
+            missing.push(#required_names);
+          }
+        )*
+        if !missing.is_empty() {
+          return ::core::result::Result::Err(#error { missing });
+        }
+
+        ::core::result::Result::Ok(#ident {
+          #(#(#cfgs)* #names: #values,)*
+        })
+      }
+    }
+
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Returns a builder with no field set.
+      #vis fn builder() -> #builder #ty_generics {
+        ::core::default::Default::default()
+      }
+    }
+  }
+}
+
+/// Checks whether the setter of a field takes the field's type itself rather than anything
+/// converting into it.
+///
+/// This is the case for primitive types, whose literals would not infer to a type converting into
+/// them, and for the target's type parameters, which would not be inferred from the argument.
+fn takes_exact_type(ty:&syn::Type, generics:&syn::Generics) -> bool {
+  const PRIMITIVES:&[&str] = &[
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128",
+    "usize",
+  ];
+
+  let syn::Type::Path(syn::TypePath { qself: None, path }) = ty else {
+    return false;
+  };
+  let Some(ident) = path.get_ident() else {
+    return false;
+  };
+  PRIMITIVES.iter().any(|primitive| ident == primitive) || generics.type_params().any(|param| &param.ident == ident)
+}
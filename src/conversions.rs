@@ -0,0 +1,289 @@
+//! Conversions between injection targets and their sources.
+//!
+//! With the `conversions` option, `#[inject_fields]` generates for a target:
+//! - `Target::from_parts(sources..., own fields...)`, assembling the target from whole sources
+//! - `Target::into_parts(self)`, splitting it back into a tuple in the same order
+//! - `Target::to_<source>(&self)` for each source, cloning the source's fields out of the target
+//! - `From<Target> for Source` for each source
+//!
+//! Each source is named after its snake case name, so `Base` becomes the `base` parameter of
+//! `from_parts` and gives the target a `to_base` method.
+//!
+//! Fields declared under `#[cfg(...)]` are not supported: the tuple returned by `into_parts` and
+//! the `Clone` bounds of `to_<source>` cannot leave out a configured-out field.
+
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Field};
+
+use crate::{
+  error::Diagnostics,
+  types::{cfg_attrs, InjectConfig, InjectSource, InjectedFields},
+};
+
+/// Generates the conversions between a target and its sources.
+///
+/// A source converts only if all of its fields made it into the target; sources injected through
+/// `only(...)` or `except(...)`, or with a field the target overrides or another source declares
+/// as well, cannot be rebuilt from the target. A field a source shares with an earlier one, both
+/// injecting the struct declaring it as in a diamond, is taken from the earlier source by
+/// `from_parts`, and cloned into the later one by `into_parts`. Neither a flattened source nor the
+/// target itself may have fields under `#[cfg(...)]`; an embedded source converts whatever its
+/// own fields are.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `injected` - The fields each source added to the target, in configuration order
+/// * `own_fields` - Fields declared on the target itself
+/// * `target` - The target struct, with its fields injected
+///
+/// # Returns
+///
+/// * `Ok(TokenStream)` with the inherent methods and `From` impls
+/// * `Err(syn::Error)` at each source, or own field under `#[cfg(...)]`, that cannot be converted
+pub fn conversion_impls(
+  config:&InjectConfig,
+  injected:&[InjectedFields],
+  own_fields:&[Field],
+  target:&syn::DeriveInput,
+) -> syn::Result<TokenStream> {
+  let target_fields = match &target.data {
+    syn::Data::Struct(syn::DataStruct {
+      fields: syn::Fields::Named(fields),
+      ..
+    }) => &fields.named,
+    _ => unreachable!("injection targets are structs with named fields"),
+  };
+  let is_among = |members:&[(syn::Member, syn::Member)], field:&Field| {
+    members.iter().any(
+      |(_, target_field)| matches!(target_field, syn::Member::Named(target) if field.ident.as_ref() == Some(target)),
+    )
+  };
+
+  let mut diagnostics = Diagnostics::default();
+  let mut names = HashSet::new();
+  for (injection, fields) in config.structs.iter().zip(injected) {
+    let source = &injection.path.path.segments.last().unwrap().ident;
+    if !names.insert(injection.snake_case_name()) {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "Cannot generate conversions for {}: it is injected into {} more than once",
+          source, target.ident
+        ),
+      ));
+    } else if !fields.complete {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "Cannot generate conversions for {}: not all of its fields are injected into {}",
+          source, target.ident
+        ),
+      ));
+    } else if let Some(field) = target_fields.iter().find(|field| {
+      !cfg_attrs(&field.attrs).is_empty() && (is_among(&fields.fields, field) || is_among(&fields.shared, field))
+    }) {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "Cannot generate conversions for {}: its field '{}' is declared under #[cfg(...)]",
+          source,
+          field.ident.as_ref().unwrap()
+        ),
+      ));
+    }
+  }
+  for field in own_fields.iter().filter(|field| !cfg_attrs(&field.attrs).is_empty()) {
+    diagnostics.push(syn::Error::new(
+      field.ident.span(),
+      format!(
+        "Cannot generate conversions for {}: its field '{}' is declared under #[cfg(...)]",
+        target.ident,
+        field.ident.as_ref().unwrap()
+      ),
+    ));
+  }
+  diagnostics.finish()?;
+
+  let ident = &target.ident;
+  let vis = &target.vis;
+  let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+
+  let source_names:Vec<_> = config.structs.iter().map(InjectSource::snake_case_name).collect();
+  let source_types:Vec<_> = config.structs.iter().map(|injection| &injection.path).collect();
+  let own_names:Vec<_> = own_fields.iter().map(|field| &field.ident).collect();
+  let own_types:Vec<_> = own_fields.iter().map(|field| &field.ty).collect();
+
+  // Target fields initialized from the `from_parts` parameter of each source
+  let from_sources = source_names.iter().zip(injected).flat_map(|(name, fields)| {
+    match &fields.embedded {
+      Some(embedded) => vec![quote!(#embedded: #name)],
+      None => {
+        fields
+          .fields
+          .iter()
+          .map(|(source_field, target_field)| quote!(#target_field: #name.#source_field))
+          .collect()
+      }
+    }
+  });
+
+  let into_sources:Vec<_> = config
+    .structs
+    .iter()
+    .zip(injected)
+    .map(|(injection, fields)| {
+      construct_source(
+        injection,
+        fields,
+        |field| quote!(target.#field),
+        |field| quote!(target.#field),
+      )
+    })
+    .collect();
+  let to_sources = config.structs.iter().zip(injected).map(|(injection, fields)| {
+    let clone = |field:&syn::Member| quote!(::core::clone::Clone::clone(&self.#field));
+    construct_source(injection, fields, clone, clone)
+  });
+
+  // Splitting the target, a shared field moves into the first source and is cloned into the
+  // others beforehand
+  let shared_names:Vec<Vec<_>> = injected
+    .iter()
+    .enumerate()
+    .map(|(index, fields)| {
+      fields
+        .shared
+        .iter()
+        .map(|(_, target_field)| format_ident!("__shared_{}_{}", index, target_field))
+        .collect()
+    })
+    .collect();
+  let shared_clones = injected.iter().zip(&shared_names).flat_map(|(fields, names)| {
+    fields
+      .shared
+      .iter()
+      .zip(names)
+      .map(|((_, target_field), name)| quote!(let #name = ::core::clone::Clone::clone(&target.#target_field);))
+  });
+  let parts = config
+    .structs
+    .iter()
+    .zip(injected)
+    .zip(&shared_names)
+    .map(|((injection, fields), names)| {
+      construct_source(
+        injection,
+        fields,
+        |field| quote!(target.#field),
+        |field| {
+          let position = fields.shared.iter().position(|(_, shared)| shared == field).unwrap();
+          let name = &names[position];
+          quote!(#name)
+        },
+      )
+    });
+  let to_methods = source_names.iter().map(|name| format_ident!("to_{}", name));
+
+  // Cloning a source needs its fields to be `Clone`; the bounds are higher-ranked so that they are
+  // only checked where `to_<source>` is called, instead of ruling out conversions for sources
+  // with fields that cannot be cloned
+  let clone_bounds = injected.iter().map(|fields| {
+    let types = target_fields
+      .iter()
+      .filter(|field| {
+        fields.embedded.as_ref() == field.ident.as_ref()
+          || is_among(&fields.fields, field)
+          || is_among(&fields.shared, field)
+      })
+      .map(|field| &field.ty);
+    quote!(where #(for<'__clone> #types: ::core::clone::Clone),*)
+  });
+  let shared_types = target_fields
+    .iter()
+    .filter(|field| injected.iter().any(|fields| is_among(&fields.shared, field)))
+    .map(|field| &field.ty);
+
+  Ok(quote! {
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Assembles the struct from its sources and its own fields.
+      #[allow(clippy::too_many_arguments)]
+      #vis fn from_parts(#(#source_names: #source_types,)* #(#own_names: #own_types),*) -> Self {
+        Self {
+          #(#own_names,)*
+          #(#from_sources,)*
+        }
+      }
+
+      /// Splits the struct into its sources and its own fields.
+      #[allow(clippy::type_complexity)]
+      #vis fn into_parts(self) -> (#(#source_types,)* #(#own_types,)*)
+      where
+        #(for<'__clone> #shared_types: ::core::clone::Clone,)*
+      {
+        let target = self;
+        #(#shared_clones)*
+        (#(#parts,)* #(target.#own_names,)*)
+      }
+
+      #(
+        /// Clones the fields of this source out of the struct.
+        #vis fn #to_methods(&self) -> #source_types #clone_bounds {
+          #to_sources
+        }
+      )*
+    }
+
+    #(
+      impl #impl_generics ::core::convert::From<#ident #ty_generics> for #source_types #where_clause {
+        fn from(target: #ident #ty_generics) -> Self {
+          #into_sources
+        }
+      }
+    )*
+  })
+}
+
+/// Builds an expression constructing a source from the target's fields.
+///
+/// # Arguments
+///
+/// * `injection` - The source as listed in `#[inject_fields(...)]`
+/// * `fields` - The fields the source added to the target
+/// * `value` - Expression reading a field of the target, given its name in the target
+/// * `shared_value` - Expression reading a field the source shares with an earlier one, given its
+///   name in the target
+fn construct_source(
+  injection:&InjectSource,
+  fields:&InjectedFields,
+  value:impl Fn(&syn::Member) -> TokenStream,
+  shared_value:impl Fn(&syn::Member) -> TokenStream,
+) -> TokenStream {
+  if let Some(embedded) = &fields.embedded {
+    return value(&syn::Member::Named(embedded.clone()));
+  }
+
+  // A struct expression needs its generic arguments written with turbofish
+  let mut path = injection.path.path.clone();
+  for segment in &mut path.segments {
+    if let syn::PathArguments::AngleBracketed(arguments) = &mut segment.arguments {
+      arguments.colon2_token = Some(Default::default());
+    }
+  }
+
+  let (source_fields, values):(Vec<_>, Vec<_>) = fields
+    .fields
+    .iter()
+    .map(|(source_field, target_field)| (source_field, value(target_field)))
+    .chain(
+      fields
+        .shared
+        .iter()
+        .map(|(source_field, target_field)| (source_field, shared_value(target_field))),
+    )
+    .unzip();
+  quote!(#path { #(#source_fields: #values),* })
+}
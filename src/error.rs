@@ -1,58 +1,101 @@
 //! Error handling utilities for compilation errors.
 //!
-//! This module provides functionality for generating compile-time errors
-//! when invalid field injections are detected.
+//! Errors are built as `syn::Error`s spanned to the offending tokens, such as a source path in
+//! `#[inject_fields(...)]` or a field named in its options, and collected so that all of them are
+//! reported at once. Misspelled options and field names get "did you mean" suggestions; the
+//! injectable structs that exist are unknown to an expansion, so misspelled sources are left to
+//! rustc, which reports them as missing macros with a similarly named one.
 
-use proc_macro::TokenStream;
-use quote::quote;
-
-/// Generates a compile-time error with the given message.
-///
-/// This function creates a TokenStream that will cause the Rust compiler
-/// to emit an error at the macro expansion site.
-///
-/// # Arguments
-///
-/// * `msg` - The error message to display
-///
-/// # Returns
-///
-/// A `TokenStream` that will generate a compilation error
+/// Collects errors so that every problem found is reported at once, each at its own span.
 ///
 /// # Examples
 ///
 /// ```rust,ignore
-/// # use crate::error::compile_error;
-/// let error = compile_error("Invalid field injection");
+/// # use crate::error::Diagnostics;
+/// let mut diagnostics = Diagnostics::default();
+/// diagnostics.push(syn::Error::new(first.span(), "Unknown field 'identifier'"));
+/// diagnostics.push(syn::Error::new(second.span(), "Unknown field 'nmae'"));
+/// diagnostics.finish()?; // reports both errors
 /// ```
-pub fn compile_error(msg:&str) -> TokenStream {
-  TokenStream::from(quote! {
-      compile_error!(#msg);
-  })
+#[derive(Default)]
+pub struct Diagnostics {
+  error:Option<syn::Error>,
 }
 
-/* Commented implementation for potential future use
-/// Generates multiple compile-time errors.
+impl Diagnostics {
+  /// Adds an error to the ones already collected.
+  pub fn push(&mut self, error:syn::Error) {
+    match &mut self.error {
+      Some(existing) => existing.combine(error),
+      None => self.error = Some(error),
+    }
+  }
+
+  /// Takes the value of a result, or collects its error.
+  pub fn take<T>(&mut self, result:syn::Result<T>) -> Option<T> { result.map_err(|error| self.push(error)).ok() }
+
+  /// Returns all collected errors combined, if there are any.
+  pub fn finish(self) -> syn::Result<()> {
+    match self.error {
+      Some(error) => Err(error),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Finds the candidate closest to a misspelled name, for "did you mean" suggestions.
 ///
-/// This function would create a TokenStream that generates multiple
-/// compilation errors at once.
+/// A candidate is suggested if it is within an edit distance of a quarter of the name's length,
+/// and at least one.
 ///
-/// # Arguments
+/// # Examples
 ///
-/// * `errors` - Vector of error messages
+/// ```rust,ignore
+/// # use crate::error::did_you_mean;
+/// assert_eq!(did_you_mean("acessors", ["export", "accessors", "views"]), Some("accessors"));
+/// assert_eq!(did_you_mean("title", ["export", "accessors", "views"]), None);
+/// ```
+pub fn did_you_mean<'a>(name:&str, candidates:impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+  let threshold = (name.chars().count() / 4).max(1);
+  candidates
+    .into_iter()
+    .map(|candidate| (edit_distance(name, candidate), candidate))
+    .filter(|(distance, _)| *distance <= threshold)
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, candidate)| candidate)
+}
+
+/// Computes the edit distance between two strings, counting insertions, deletions,
+/// substitutions and transpositions of adjacent characters as one edit each.
+fn edit_distance(a:&str, b:&str) -> usize {
+  let a:Vec<char> = a.chars().collect();
+  let b:Vec<char> = b.chars().collect();
+  let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+  for (i, row) in distances.iter_mut().enumerate() {
+    row[0] = i;
+  }
+  for (j, distance) in distances[0].iter_mut().enumerate() {
+    *distance = j;
+  }
+
+  for i in 1..=a.len() {
+    for j in 1..=b.len() {
+      let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+      let mut distance = substitution.min(distances[i - 1][j] + 1).min(distances[i][j - 1] + 1);
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        distance = distance.min(distances[i - 2][j - 2] + 1);
+      }
+      distances[i][j] = distance;
+    }
+  }
+  distances[a.len()][b.len()]
+}
+
+/// Formats a "did you mean" hint to append to an error message, if a candidate is close enough.
 ///
 /// # Returns
 ///
-/// A `TokenStream` that will generate multiple compilation errors
-pub fn compile_errors(errors: Vec<String>) -> TokenStream {
-    let error_tokens = errors.iter().map(|msg| {
-        quote! {
-            compile_error!(#msg);
-        }
-    });
-
-    TokenStream::from(quote! {
-        #(#error_tokens)*
-    })
+/// `"; did you mean 'name'?"`, or an empty string if no candidate is close
+pub fn suggestion<'a>(name:&str, candidates:impl IntoIterator<Item = &'a str>) -> String {
+  did_you_mean(name, candidates).map_or_else(String::new, |candidate| format!("; did you mean '{}'?", candidate))
 }
-*/
@@ -0,0 +1,417 @@
+//! Generic parameter substitution for injected field types.
+//!
+//! This module provides functionality for:
+//! - Mapping the generic parameters of a source struct to the arguments given in `#[inject_fields(...)]`
+//! - Substituting those arguments into the field types and mixed-in methods of the source
+//! - Carrying the bounds of the source's parameters over to the target
+//!
+//! Substitution works on the parsed `syn::Type` rather than its string representation, so only
+//! path segments that are exactly a generic parameter are replaced. Type, lifetime and const
+//! parameters are all supported.
+
+use std::collections::HashMap;
+
+use quote::ToTokens;
+use syn::{parse_quote, visit_mut::VisitMut};
+
+/// Replaces generic parameters with concrete arguments throughout a type.
+///
+/// Only a path whose first segment is exactly a parameter is rewritten; identifiers that merely
+/// contain a parameter name (`Tx` or `Timestamp` for `T`) and later segments of a path
+/// (`foo::T`) are left alone. The visitor descends into generic arguments, qualified paths,
+/// references, slices, arrays, tuples, trait objects, `impl Trait` and fn pointers.
+///
+/// A shorthand associated type path such as `T::Output` is rewritten to the qualified form
+/// `<Arg as Trait>::Output`, since the shorthand is only valid for a type parameter. The trait is
+/// the one bound of `T` that may declare associated types, leaving out the likes of `Clone` or
+/// `Debug`; with none or several, the type is replaced by an error naming the parameter.
+///
+/// # Fields
+///
+/// * `types` - Concrete type for each generic type parameter, keyed by parameter name
+/// * `bounds` - Trait bounds of each generic type parameter, keyed by parameter name
+/// * `lifetimes` - Lifetime for each lifetime parameter, keyed by parameter name without the `'`
+/// * `consts` - Value for each const parameter, keyed by parameter name
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// # use syn::parse_quote;
+/// # use crate::generics::GenericSubstitution;
+/// let generics:syn::Generics = parse_quote!(<'a, T, const N: usize>);
+/// let substitution = GenericSubstitution::new(&generics, &parse_quote!(<'static, u8, 16>));
+/// let ty = substitution.apply(&parse_quote!(&'a [Option<T>; N]));
+/// assert_eq!(ty, parse_quote!(&'static [Option<u8>; 16]));
+/// ```
+pub struct GenericSubstitution {
+  types:    HashMap<String, syn::Type>,
+  bounds:   HashMap<String, Vec<syn::Path>>,
+  lifetimes:HashMap<String, syn::Lifetime>,
+  consts:   HashMap<String, syn::Expr>,
+}
+
+impl GenericSubstitution {
+  /// Pairs the generic parameters of a source with the arguments it is instantiated with.
+  ///
+  /// Lifetime arguments are paired with lifetime parameters, and the remaining arguments with
+  /// the type and const parameters in declaration order. Pairing is positional because a const
+  /// argument naming a constant, as in `Buf<SIZE>`, is indistinguishable from a type argument.
+  ///
+  /// # Arguments
+  ///
+  /// * `generics` - Generics of the source struct, including bounds and where-clauses
+  /// * `args` - Arguments of the source path in `#[inject_fields(...)]`
+  ///
+  /// # Returns
+  ///
+  /// A substitution replacing each parameter with its argument
+  pub fn new(generics:&syn::Generics, args:&syn::PathArguments) -> Self {
+    let args:Vec<&syn::GenericArgument> = match args {
+      syn::PathArguments::AngleBracketed(args) => args.args.iter().collect(),
+      _ => Vec::new(),
+    };
+    let (lifetime_args, other_args):(Vec<_>, Vec<_>) = args
+      .into_iter()
+      .partition(|arg| matches!(arg, syn::GenericArgument::Lifetime(_)));
+
+    let mut substitution = GenericSubstitution {
+      types:    HashMap::new(),
+      bounds:   HashMap::new(),
+      lifetimes:HashMap::new(),
+      consts:   HashMap::new(),
+    };
+
+    for (param, arg) in generics.lifetimes().zip(lifetime_args) {
+      if let syn::GenericArgument::Lifetime(lifetime) = arg {
+        substitution
+          .lifetimes
+          .insert(param.lifetime.ident.to_string(), lifetime.clone());
+      }
+    }
+
+    // Parameters left out of the arguments take their defaults, as in `Pair<u8>` for `Pair<A, B = String>`
+    let params = generics
+      .params
+      .iter()
+      .filter(|param| !matches!(param, syn::GenericParam::Lifetime(_)));
+    let defaults:Vec<syn::GenericArgument> = params
+      .clone()
+      .skip(other_args.len())
+      .map_while(|param| {
+        match param {
+          syn::GenericParam::Type(param) => param.default.clone().map(syn::GenericArgument::Type),
+          syn::GenericParam::Const(param) => param.default.clone().map(syn::GenericArgument::Const),
+          syn::GenericParam::Lifetime(_) => None,
+        }
+      })
+      .collect();
+
+    for (param, arg) in params.zip(other_args.into_iter().chain(&defaults)) {
+      match (param, arg) {
+        (syn::GenericParam::Type(param), syn::GenericArgument::Type(ty)) => {
+          let name = param.ident.to_string();
+          substitution
+            .bounds
+            .entry(name.clone())
+            .or_default()
+            .extend(trait_paths(&param.bounds));
+          substitution.types.insert(name, ty.clone());
+        }
+        (syn::GenericParam::Const(param), syn::GenericArgument::Const(expr)) => {
+          substitution.consts.insert(param.ident.to_string(), expr.clone());
+        }
+        // A constant passed by name parses as a type argument
+        (syn::GenericParam::Const(param), syn::GenericArgument::Type(syn::Type::Path(path))) => {
+          substitution.consts.insert(
+            param.ident.to_string(),
+            syn::Expr::Path(syn::ExprPath {
+              attrs:Vec::new(),
+              qself:path.qself.clone(),
+              path: path.path.clone(),
+            }),
+          );
+        }
+        _ => {}
+      }
+    }
+
+    // Bounds from the where-clause on a bare parameter, e.g. `where T: Codec`
+    for predicate in generics.where_clause.iter().flat_map(|clause| &clause.predicates) {
+      if let syn::WherePredicate::Type(predicate) = predicate {
+        if let syn::Type::Path(syn::TypePath { qself: None, path }) = &predicate.bounded_ty {
+          if let Some(param_bounds) = path
+            .get_ident()
+            .and_then(|ident| substitution.bounds.get_mut(&ident.to_string()))
+          {
+            param_bounds.extend(trait_paths(&predicate.bounds));
+          }
+        }
+      }
+    }
+
+    substitution
+  }
+
+  /// Returns the bounds a source places on its generic parameters, in terms of the arguments.
+  ///
+  /// Both inline bounds (`T: Display`) and where-clauses are included. Added to the target's
+  /// where-clause, they carry over the source's requirements to parameters the target forwards,
+  /// and have rustc check them for concrete arguments. Relaxed bounds (`?Sized`) are left out:
+  /// they lift a requirement rather than add one, and rustc accepts them only on the parameters
+  /// of the item declaring them.
+  ///
+  /// # Arguments
+  ///
+  /// * `generics` - Generics of the source struct this substitution was built from
+  ///
+  /// # Returns
+  ///
+  /// The substituted where-predicates of the source
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// # use syn::parse_quote;
+  /// # use crate::generics::GenericSubstitution;
+  /// let generics:syn::Generics = parse_quote!(<T: Display> where T: Clone);
+  /// let substitution = GenericSubstitution::new(&generics, &parse_quote!(<Vec<U>>));
+  /// // Yields `Vec<U>: Display` and `Vec<U>: Clone`
+  /// let predicates = substitution.predicates(&generics);
+  /// ```
+  pub fn predicates(&self, generics:&syn::Generics) -> Vec<syn::WherePredicate> {
+    let inline = generics
+      .params
+      .iter()
+      .filter_map(|param| -> Option<syn::WherePredicate> {
+        match param {
+          syn::GenericParam::Lifetime(param) if !param.bounds.is_empty() => {
+            let (lifetime, bounds) = (&param.lifetime, &param.bounds);
+            Some(parse_quote!(#lifetime: #bounds))
+          }
+          syn::GenericParam::Type(param) => {
+            let ident = &param.ident;
+            let bounds = required_bounds(&param.bounds);
+            (!bounds.is_empty()).then(|| parse_quote!(#ident: #(#bounds)+*))
+          }
+          _ => None,
+        }
+      });
+    let clauses = generics
+      .where_clause
+      .iter()
+      .flat_map(|clause| clause.predicates.iter().cloned())
+      .filter_map(|mut predicate| {
+        if let syn::WherePredicate::Type(predicate) = &mut predicate {
+          predicate.bounds = required_bounds(&predicate.bounds).into_iter().cloned().collect();
+          if predicate.bounds.is_empty() {
+            return None;
+          }
+        }
+        Some(predicate)
+      });
+
+    inline
+      .chain(clauses)
+      .map(|mut predicate| {
+        let mut visitor = self;
+        visitor.visit_where_predicate_mut(&mut predicate);
+        predicate
+      })
+      .collect()
+  }
+
+  /// Checks whether the substitution provides an argument for every parameter in `generics`.
+  pub fn covers(&self, generics:&syn::Generics) -> bool {
+    generics.params.iter().all(|param| {
+      match param {
+        syn::GenericParam::Lifetime(param) => self.lifetimes.contains_key(&param.lifetime.ident.to_string()),
+        syn::GenericParam::Type(param) => self.types.contains_key(&param.ident.to_string()),
+        syn::GenericParam::Const(param) => self.consts.contains_key(&param.ident.to_string()),
+      }
+    })
+  }
+
+  /// Checks whether the substitution replaces nothing.
+  pub fn is_empty(&self) -> bool { self.types.is_empty() && self.lifetimes.is_empty() && self.consts.is_empty() }
+
+  /// Applies the substitution to a type.
+  ///
+  /// # Arguments
+  ///
+  /// * `ty` - The type to substitute into
+  ///
+  /// # Returns
+  ///
+  /// The type with every generic parameter replaced by its argument
+  pub fn apply(&self, ty:&syn::Type) -> syn::Type {
+    let mut ty = ty.clone();
+    let mut visitor = self;
+    visitor.visit_type_mut(&mut ty);
+    ty
+  }
+
+  /// Returns the name of the parameter a path starts with, if it may be one.
+  fn leading_param(path:&syn::Path) -> Option<String> {
+    let first = path.segments.first()?;
+    if path.leading_colon.is_some() || !first.arguments.is_none() {
+      return None;
+    }
+    Some(first.ident.to_string())
+  }
+
+  /// Returns the value of a const parameter named by a path, wrapped in braces where needed.
+  fn const_value(&self, path:&syn::Path) -> Option<syn::Expr> {
+    let name = path.get_ident()?.to_string();
+    let value = self.consts.get(&name)?;
+    Some(match value {
+      syn::Expr::Lit(_) | syn::Expr::Path(_) | syn::Expr::Block(_) => value.clone(),
+      _ => parse_quote!({ #value }),
+    })
+  }
+}
+
+impl VisitMut for &GenericSubstitution {
+  fn visit_type_mut(&mut self, ty:&mut syn::Type) {
+    if let syn::Type::Path(type_path) = ty {
+      if type_path.qself.is_none() {
+        let name = GenericSubstitution::leading_param(&type_path.path);
+        if let Some(replacement) = name.as_ref().and_then(|name| self.types.get(name)) {
+          let rest:Vec<_> = type_path.path.segments.iter().skip(1).collect();
+          if rest.is_empty() {
+            *ty = replacement.clone();
+            return;
+          }
+
+          // `T::Output` becomes `<Arg as Trait>::Output`, naming the trait it was resolved through
+          let name = name.unwrap();
+          let bounds:Vec<_> = self
+            .bounds
+            .get(&name)
+            .into_iter()
+            .flatten()
+            .filter(|bound| may_declare_types(bound))
+            .collect();
+          *ty = match bounds.as_slice() {
+            [bound] => parse_quote!(<#replacement as #bound>::#(#rest)::*),
+            bounds => {
+              let path = type_path.path.to_token_stream().to_string().replace(" :: ", "::");
+              let message = format!(
+                "Cannot resolve {} for the argument of {}, which has {} bounds that may declare {}; bound {} by \
+                 exactly one such trait, or write <{} as Trait>::{}",
+                path,
+                name,
+                bounds.len(),
+                rest[0].ident,
+                name,
+                name,
+                rest
+                  .iter()
+                  .map(|segment| segment.ident.to_string())
+                  .collect::<Vec<_>>()
+                  .join("::")
+              );
+              let error = syn::Error::new_spanned(&type_path.path, message).to_compile_error();
+              parse_quote!(#error)
+            }
+          };
+          return;
+        }
+      }
+    }
+
+    syn::visit_mut::visit_type_mut(self, ty);
+  }
+
+  fn visit_generic_argument_mut(&mut self, arg:&mut syn::GenericArgument) {
+    // A const parameter passed on, as in `Inner<N>`, parses as a type argument
+    if let syn::GenericArgument::Type(syn::Type::Path(syn::TypePath { qself: None, path })) = arg {
+      if let Some(value) = self.const_value(path) {
+        *arg = syn::GenericArgument::Const(value);
+        return;
+      }
+    }
+
+    syn::visit_mut::visit_generic_argument_mut(self, arg);
+  }
+
+  fn visit_expr_mut(&mut self, expr:&mut syn::Expr) {
+    if let syn::Expr::Path(syn::ExprPath { qself: None, path, .. }) = expr {
+      if let Some(value) = self.const_value(path) {
+        *expr = value;
+        return;
+      }
+
+      // `T::default()` in a mixed-in method becomes `<Arg>::default()`, leaving the trait to
+      // method resolution since the path may name any associated function
+      let name = GenericSubstitution::leading_param(path).filter(|_| path.segments.len() > 1);
+      if let Some(replacement) = name.and_then(|name| self.types.get(&name)) {
+        let rest = path.segments.iter().skip(1);
+        *expr = parse_quote!(<#replacement>::#(#rest)::*);
+        return;
+      }
+    }
+
+    syn::visit_mut::visit_expr_mut(self, expr);
+  }
+
+  fn visit_lifetime_mut(&mut self, lifetime:&mut syn::Lifetime) {
+    if let Some(replacement) = self.lifetimes.get(&lifetime.ident.to_string()) {
+      *lifetime = replacement.clone();
+    }
+  }
+}
+
+/// Traits of the standard library that declare no associated types.
+const TRAITS_WITHOUT_TYPES:&[&str] = &[
+  "Clone",
+  "Copy",
+  "Send",
+  "Sync",
+  "Sized",
+  "Unpin",
+  "Debug",
+  "Display",
+  "Default",
+  "Eq",
+  "PartialEq",
+  "Ord",
+  "PartialOrd",
+  "Hash",
+];
+
+/// Checks whether a trait bound may declare associated types, as far as its name tells.
+fn may_declare_types(bound:&syn::Path) -> bool {
+  bound
+    .segments
+    .last()
+    .is_none_or(|segment| !TRAITS_WITHOUT_TYPES.contains(&segment.ident.to_string().as_str()))
+}
+
+/// Collects the bounds of a generic parameter that require something, skipping relaxed bounds like
+/// `?Sized`.
+fn required_bounds<'a>(bounds:impl IntoIterator<Item = &'a syn::TypeParamBound>) -> Vec<&'a syn::TypeParamBound> {
+  bounds
+    .into_iter()
+    .filter(|bound| {
+      match bound {
+        syn::TypeParamBound::Trait(bound) => !matches!(bound.modifier, syn::TraitBoundModifier::Maybe(_)),
+        _ => true,
+      }
+    })
+    .collect()
+}
+
+/// Collects the trait paths among the bounds of a generic parameter, skipping `?Sized` and lifetimes.
+fn trait_paths<'a>(bounds:impl IntoIterator<Item = &'a syn::TypeParamBound>) -> Vec<syn::Path> {
+  bounds
+    .into_iter()
+    .filter_map(|bound| {
+      match bound {
+        syn::TypeParamBound::Trait(bound) if matches!(bound.modifier, syn::TraitBoundModifier::None) => {
+          Some(bound.path.clone())
+        }
+        _ => None,
+      }
+    })
+    .collect()
+}
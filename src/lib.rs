@@ -7,10 +7,16 @@
 //! # Features
 //!
 //! - Respects Rust's visibility rules (`pub`, `pub(crate)`, private)
-//! - Supports generic types with concrete type resolution
+//! - Supports generic types, lifetimes and const generics with concrete argument resolution
 //! - Compile-time dependency injection and validation
 //! - Detects circular dependencies and invalid injections
 //! - Supports nested/transitive injections
+//! - Resolves sources by path, independent of declaration order
+//! - Injects sources exported from other crates
+//! - Stateless expansion, safe for incremental builds and rust-analyzer
+//! - Carries field attributes and doc comments over to injected fields
+//! - Injects fields into the variants of enums, with accessors reading them from any variant
+//! - Mixes the methods of a source's `#[injectable]` impl block into the structs injecting it
 //!
 //! # Basic Usage
 //!
@@ -49,6 +55,30 @@
 //! }
 //! ```
 //!
+//! A generic target can pass its own parameters on to a source, and inherits the source's bounds on them:
+//!
+//! ```rust,ignore
+//! #[inject_fields(GenericBase<T>)]
+//! pub struct Wrapper<T: Clone> {
+//!     pub name: String,
+//! }
+//! ```
+//!
+//! Lifetime and const parameters are substituted as well:
+//!
+//! ```rust,ignore
+//! #[injectable]
+//! pub struct Buf<'a, const N: usize> {
+//!     pub bytes: [u8; N],
+//!     pub label: &'a str,
+//! }
+//!
+//! #[inject_fields(Buf<'a, 16>)]
+//! pub struct Packet<'a> {
+//!     pub id: u32,
+//! }
+//! ```
+//!
 //! ## Nested Injections
 //!
 //! ```rust,ignore
@@ -67,6 +97,18 @@
 //! pub struct C {
 //!     pub description: String,
 //! }
+//!
+//! #[injectable]
+//! #[inject_fields(A)]
+//! pub struct D {
+//!     pub version: u32,
+//! }
+//!
+//! // A arrives through both B and D, and is injected once
+//! #[inject_fields(B, D)]
+//! pub struct E {
+//!     pub title: String,
+//! }
 //! ```
 //!
 //! ## Visibility Rules
@@ -82,41 +124,281 @@
 //!     }
 //! }
 //!
-//! #[inject_fields(inner::Private)]
+//! // `id` is private to `inner`, so it has to be left out
+//! #[inject_fields(inner::Private(except(id)))]
 //! pub struct Public {
 //!     pub value: String,
-//!     // Can access `name` and `age`, but not `id`
+//!     // Has `name` and `age`
+//! }
+//! ```
+//!
+//! ## Adjusting Visibility
+//!
+//! ```rust,ignore
+//! #[injectable]
+//! pub struct Auditable {
+//!     pub created_by: String,
+//!     #[inject(vis = pub(crate))]
+//!     pub updated_by: String, // injected as `pub(crate)`
+//! }
+//!
+//! #[inject_fields(Auditable(vis = pub(crate)))]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//! ```
+//!
+//! ## Renaming and Prefixing Fields
+//!
+//! ```rust,ignore
+//! #[inject_fields(Address(prefix = "billing_"), Address(prefix = "shipping_"), Base(rename(id = base_id)))]
+//! pub struct Order {
+//!     pub id: String,
+//! }
+//! ```
+//!
+//! ## Selecting Fields
+//!
+//! ```rust,ignore
+//! // Injects created_at only; `except(created_by)` would inject everything else instead
+//! #[inject_fields(Auditable(only(created_at)))]
+//! pub struct Event {
+//!     pub name: String,
+//! }
+//! ```
+//!
+//! ## Overriding Fields
+//!
+//! ```rust,ignore
+//! #[inject_fields(Base)]
+//! pub struct Document {
+//!     #[inject(override)]
+//!     id: String, // replaces the id injected from Base
+//! }
+//! ```
+//!
+//! ## Embedding Sources
+//!
+//! ```rust,ignore
+//! // Adds `pub base: Base`, plus `Deref` and `DerefMut` to it, so `document.id` still works
+//! #[inject_fields(Base(embed))]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//! ```
+//!
+//! ## Tuple and Unit Structs
+//!
+//! ```rust,ignore
+//! #[injectable]
+//! pub struct Point(pub i32, pub i32);
+//!
+//! // Labeled(&'static str, i32, i32), or the fields of Point first with `Point(prepend)`
+//! #[inject_fields(Point)]
+//! pub struct Labeled(pub &'static str);
+//!
+//! // A unit target holds only the injected fields
+//! #[inject_fields(Base, Auditable)]
+//! pub struct Record;
+//! ```
+//!
+//! ## Enum Targets
+//!
+//! ```rust,ignore
+//! // Every struct-like or unit variant gets the fields of Envelope, and the enum gets `id()`,
+//! // `id_mut()`, `sent_at()` and `sent_at_mut()`, returning `Option`s as `Ping` lacks the fields
+//! #[inject_fields(Envelope)]
+//! pub enum Event {
+//!     Created { name: String },
+//!     Deleted,
+//!     #[inject(skip)]
+//!     Ping(u8),
+//! }
+//! ```
+//!
+//! ## Accessor Traits
+//!
+//! ```rust,ignore
+//! #[injectable(accessors)]
+//! pub struct Auditable {
+//!     pub created_by: String,
+//! }
+//!
+//! // Works for Auditable and every struct injecting it
+//! fn audit(item: &impl HasAuditable) -> &str {
+//!     item.created_by()
+//! }
+//! ```
+//!
+//! ## Views
+//!
+//! ```rust,ignore
+//! #[injectable(views)]
+//! pub struct Base {
+//!     pub id: u64,
+//! }
+//!
+//! // `document.as_base_ref()` and `document.as_base_mut()` borrow the fields of Base
+//! #[inject_fields(Base)]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//!
+//! fn describe(base: BaseRef<'_>) -> String {
+//!     format!("#{}", base.id)
+//! }
+//! ```
+//!
+//! ## Conversions
+//!
+//! ```rust,ignore
+//! // Generates `from_parts`, `into_parts`, `to_base` and `From<Document> for Base`
+//! #[inject_fields(Base, conversions)]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//! ```
+//!
+//! ## Builder
+//!
+//! ```rust,ignore
+//! #[inject_fields(Base, builder)]
+//! pub struct Document {
+//!     #[inject(default = "Untitled".to_string())]
+//!     pub title: String,
+//! }
+//!
+//! let document = Document::builder().id(1).build()?;
+//! ```
+//!
+//! ## Method Mixins
+//!
+//! ```rust,ignore
+//! #[injectable(methods)]
+//! pub struct Auditable {
+//!     pub updated_at: u64,
+//! }
+//!
+//! #[injectable]
+//! impl Auditable {
+//!     pub fn touch(&mut self, now: u64) {
+//!         self.updated_at = now;
+//!     }
+//! }
+//!
+//! // Document gets `touch`, setting `modified_at`
+//! #[inject_fields(Auditable(rename(updated_at = modified_at)))]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//! ```
+//!
+//! ## Field Attributes
+//!
+//! ```rust,ignore
+//! #[injectable]
+//! pub struct Base {
+//!     /// Unique identifier
+//!     #[serde(rename = "ID")]
+//!     pub id: u64,
+//! }
+//!
+//! // `id` is injected with its doc comment; `strip_attrs(serde)` drops its `serde` attribute
+//! #[inject_fields(Base, strip_attrs(serde))]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//! ```
+//!
+//! ## Cross-crate Injection
+//!
+//! ```rust,ignore
+//! // In the `models` crate
+//! pub mod audit {
+//!     #[injectable(export)]
+//!     pub struct Auditable {
+//!         pub created_by: UserId,
+//!         pub created_at: u64,
+//!     }
+//! }
+//!
+//! // In a crate depending on `models`
+//! #[inject_fields(models::audit::Auditable)]
+//! pub struct Comment {
+//!     pub body: String,
 //! }
 //! ```
 //!
 //! # Limitations
 //!
-//! 1. Only works with named struct fields (not tuple structs)
-//! 2. Cannot inject fields into enums
-//! 3. Source structs must be marked with `#[injectable]` before use in `#[inject_fields]`
-//! 4. Injected fields maintain their original visibility rules
-//! 5. Generic types require concrete type specifications in `#[inject_fields]`
+//! 1. Tuple structs inject into tuple structs only, and named structs into named structs only
+//! 2. Enums can receive fields but not be sources, and the fields of their variants take the
+//!    enum's visibility
+//! 3. Source structs must be marked with `#[injectable]` and be nameable from the target's module
+//! 4. Relative visibilities of injected fields are resolved through the path the source is named by, so a source
+//!    whose fields such a visibility makes visible beyond the target's module must be named through the module
+//!    that defines it rather than a `use` import
+//! 5. Generic sources require an argument for each parameter without a default in `#[inject_fields]`, either a
+//!    concrete type or a parameter of the target
+//! 6. Exported sources must be named through the module that defines them (e.g. `models::audit::Auditable`
+//!    rather than a re-export), since the types of their fields are published in that module
+//! 7. A source has at most one `#[injectable]` impl block, declared in the module of the source, and
+//!    its methods reach fields only through `self.field`
+//! 8. The methods of an `#[injectable]` impl block expand in the modules of the targets, so they cannot name
+//!    the items or imports of the source's module; they use `crate::` paths, the prelude or imports within the
+//!    method
 
+mod accessors;
+mod builder;
+mod conversions;
 mod error;
+mod generics;
+mod mixins;
 mod registry;
 mod types;
+mod variants;
 mod visibility;
 
+use std::collections::HashSet;
+
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, DeriveInput};
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, spanned::Spanned, DeriveInput};
 
 use crate::{
-  error::compile_error,
-  registry::{check_and_update_injection_chain, update_module_paths, validate_and_process_input},
-  types::{InjectConfig, ModuleInfo},
-  visibility::visibility_to_kind,
+  error::Diagnostics,
+  registry::{
+    check_self_injection, injectable_callback, is_inject_fields_attr, module_info_from_source, next_source_call,
+    validate_and_process_input,
+  },
+  types::{InjectConfig, InjectableConfig, InjectionState},
 };
 
 /// Marks a struct as injectable, allowing its fields to be injected into other structs.
 ///
 /// This attribute must be applied to any struct whose fields you want to inject into other structs
-/// using [`macro@inject_fields`]. The struct must have named fields (not a tuple struct).
+/// using [`macro@inject_fields`]. Structs with named fields inject into structs with named fields,
+/// tuple structs inject their positional fields into tuple structs, and unit structs inject
+/// nothing.
+///
+/// Alongside the struct, this generates a hidden callback macro that shares the struct's name and
+/// carries its definition. [`macro@inject_fields`] expands through it, so a source is found
+/// wherever its path resolves, regardless of the order in which items are declared.
+///
+/// # Options
+///
+/// - `export`: Makes the struct usable as a source from other crates. The types of its public
+///   fields are published through hidden aliases next to the struct, so that they resolve from
+///   the consuming crate.
+/// - `accessors`: Generates a `Has<Name>` trait with a getter and a `_mut` accessor for each
+///   field at least as visible as the struct, so that the trait reaches no field its users could
+///   not. It is implemented by the struct and by every struct injecting it, directly or through
+///   other sources, as long as all of those fields are injected.
+/// - `views`: Generates `<Name>Ref<'view>` and `<Name>Mut<'view>` structs borrowing each field,
+///   returned by `as_<name>_ref` and `as_<name>_mut` methods of the struct and of every struct
+///   injecting it, on the same terms as `accessors`.
+/// - `methods`: Mixes the methods of the struct's `#[injectable]` impl block into every struct
+///   injecting it. The struct becomes usable as a source once the impl block is declared.
 ///
 /// # Example
 ///
@@ -142,14 +424,38 @@ use crate::{
 ///
 /// # Visibility
 ///
-/// Fields maintain their original visibility rules when injected:
+/// Fields keep their visibility when injected, rebased onto the module of the target, unless
+/// given another one with `#[inject(vis = ...)]`. A field that is not `pub` can be widened up to
+/// `pub(crate)` this way:
 ///
 /// ```rust,ignore
 /// #[injectable]
 /// pub struct Document {
 ///     id: u64,              // Private - only accessible in same module
 ///     pub name: String,     // Public - accessible everywhere
-///     pub(crate) data: Vec<u8>, // Crate-visible
+///     #[inject(vis = pub(crate))]
+///     pub(super) data: Vec<u8>, // Crate-visible once injected
+/// }
+/// ```
+///
+/// # Impl Blocks
+///
+/// On an impl block of a struct marked `methods`, in the struct's module, the attribute mixes the
+/// block's methods and associated constants into the struct's targets. Fields are reached through
+/// `self.field`, under the names the target has them injected as, and `Self` is the target:
+///
+/// ```rust,ignore
+/// #[injectable(methods)]
+/// pub struct Counter<T> {
+///     pub count: T,
+/// }
+///
+/// #[injectable]
+/// impl<T: Copy + std::ops::Add<Output = T>> Counter<T> {
+///     pub fn add(&mut self, step: T) -> &mut Self {
+///         self.count = self.count + step;
+///         self
+///     }
 /// }
 /// ```
 ///
@@ -157,67 +463,86 @@ use crate::{
 ///
 /// This attribute will fail to compile if:
 /// - Applied to an enum or union instead of a struct
-/// - Applied to a tuple struct (must use named fields)
+/// - Given `accessors` or `views` for a tuple or unit struct
+/// - Given an unknown option
+/// - A field is made `pub` with `#[inject(vis = pub)]` without being `pub` itself
+/// - Applied with options to an impl block, or to an impl block of a struct not marked `methods`
+/// - Applied to a trait impl, an impl block not generic over all of the struct's parameters in
+///   order, or one holding items other than methods and constants
+/// - A method of the impl block constructs or destructures `Self`, or names an item through the
+///   struct's module rather than a `crate::` path, the prelude or an import of its own
 #[proc_macro_attribute]
-pub fn injectable(_attr:TokenStream, item:TokenStream) -> TokenStream {
-  let item_clone = item.clone();
-  let input = parse_macro_input!(item as DeriveInput);
-  let struct_name = input.ident.to_string();
+pub fn injectable(attr:TokenStream, item:TokenStream) -> TokenStream {
+  // An impl block is mixed into the targets of its struct, and kept as is when it cannot be
+  if let Ok(mixin) = syn::parse::<syn::ItemImpl>(item.clone()) {
+    return TokenStream::from(mixins::register_mixin(attr.into(), &mixin).unwrap_or_else(|err| {
+      let err = err.to_compile_error();
+      quote!(#mixin #err)
+    }));
+  }
 
-  let generic_params:Vec<String> = input
-    .generics
-    .params
-    .iter()
-    .filter_map(|param| {
-      if let syn::GenericParam::Type(type_param) = param {
-        Some(type_param.ident.to_string())
-      } else {
-        None
-      }
-    })
-    .collect();
+  let attr_clone = attr.clone();
+  let config = parse_macro_input!(attr_clone as InjectableConfig);
+  let mut input = parse_macro_input!(item as DeriveInput);
+
+  // A struct that also injects fields is published once its own injection has completed, so that
+  // the callback carries the complete field list
+  if let Some(position) = input.attrs.iter().rposition(is_inject_fields_attr) {
+    let attr = proc_macro2::TokenStream::from(attr);
+    let deferred:syn::Attribute = if attr.is_empty() {
+      parse_quote!(#[::injectables::injectable])
+    } else {
+      parse_quote!(#[::injectables::injectable(#attr)])
+    };
+    input.attrs.insert(position + 1, deferred);
+    return TokenStream::from(quote!(#input));
+  }
 
-  let fields = match &input.data {
+  let unsupported = match &input.data {
     syn::Data::Struct(data) => {
       match &data.fields {
-        syn::Fields::Named(fields) => fields,
-        _ => return compile_error("Only named fields are supported"),
+        syn::Fields::Named(_) => None,
+        _ if config.accessors || config.views => {
+          Some((input.ident.span(), "Accessor traits and views require named fields"))
+        }
+        _ => None,
       }
     }
-    _ => return compile_error("Only structs are supported"),
+    syn::Data::Enum(data) => Some((data.enum_token.span, "Only structs are supported")),
+    syn::Data::Union(data) => Some((data.union_token.span, "Only structs are supported")),
   };
+  if let Some((span, message)) = unsupported {
+    return TokenStream::from(syn::Error::new(span, message).to_compile_error());
+  }
 
-  let mut registry = registry::FIELD_REGISTRY.lock().unwrap();
-  let field_defs = fields
-    .named
-    .iter()
-    .map(|f| {
-      let name = f.ident.as_ref().unwrap().to_string();
-      let vis = visibility_to_kind(&f.vis);
-
-      types::FieldDef {
-        name,
-        ty:f.ty.to_token_stream().to_string(),
-        vis,
-        generic_params:generic_params.clone(),
-      }
-    })
-    .collect();
+  // Accessor traits and views provided for the struct's own sources are handed over by its
+  // injection
+  let (field_sets, attrs) = input.attrs.drain(..).partition(accessors::is_field_set_attr);
+  input.attrs = attrs;
 
-  let module_info = ModuleInfo {
-    fields:     field_defs,
-    module_path:String::new(), // Will be populated when used in inject_fields
-  };
+  // Field options are published with the definition, but are not attributes rustc knows
+  if let Err(err) = registry::field_options(&input) {
+    return TokenStream::from(err.to_compile_error());
+  }
+  let callback = injectable_callback(&input, &config, field_sets);
+  registry::strip_field_options(&mut input);
 
-  registry.insert(struct_name, module_info);
-  item_clone
+  let accessor_trait = config.accessors.then(|| accessors::accessor_trait(&input));
+  let views = config.views.then(|| accessors::view_structs(&input));
+  TokenStream::from(quote! {
+    #input
+    #accessor_trait
+    #views
+    #callback
+  })
 }
 
 /// Injects fields from one or more injectable structs into the target struct.
 ///
 /// This attribute copies fields from source structs marked with [`macro@injectable`] into the target struct.
 /// Multiple source structs can be specified, separated by commas. For generic source structs,
-/// concrete types must be specified.
+/// arguments must be specified: concrete types, or parameters of the target itself, in which
+/// case the target inherits the bounds the source places on them.
 ///
 /// # Examples
 ///
@@ -261,75 +586,317 @@ pub fn injectable(_attr:TokenStream, item:TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// Injected fields can be renamed per source, with options in parentheses after the source.
+/// `prefix = "..."` prefixes every field of the source and `rename(field = new_name)` renames
+/// individual fields, taking precedence over the prefix:
+/// ```rust,ignore
+/// #[inject_fields(Address(prefix = "billing_"), Address(prefix = "shipping_"), Base(rename(id = base_id)))]
+/// pub struct Order {
+///     pub id: String,
+/// }
+/// ```
+///
+/// Each combination of source, generic arguments and options is a separate injection, so
+/// `Pair<i32>(prefix = "num_"), Pair<String>(prefix = "text_")` injects both instantiations.
+///
+/// `only(...)` and `except(...)` select which fields of a source are injected:
+/// ```rust,ignore
+/// #[inject_fields(Auditable(only(created_at)), Base(except(version)))]
+/// pub struct Event {
+///     pub name: String,
+/// }
+/// ```
+///
+/// A field of the target replaces the field of the same name injected by a source if marked
+/// `#[inject(override)]`, and is an error otherwise:
+/// ```rust,ignore
+/// #[inject_fields(Base)]
+/// pub struct Document {
+///     #[inject(override)]
+///     id: String,
+/// }
+/// ```
+///
+/// `vis = ...` gives every field injected from a source another visibility, written from the
+/// module of the target. Fields that are not `pub` in the source can be widened up to
+/// `pub(crate)`:
+/// ```rust,ignore
+/// #[inject_fields(Auditable(vis = pub(crate)))]
+/// pub struct Document {
+///     pub title: String,
+/// }
+/// ```
+///
+/// Tuple structs inject their positional fields into tuple targets, after the target's own fields
+/// or, with `prepend`, before them. A unit target becomes a struct with the injected fields:
+/// ```rust,ignore
+/// #[inject_fields(Tagged<u8>(prepend), Point)]
+/// pub struct Labeled(pub &'static str);  // Labeled(u8, &'static str, i32, i32)
+///
+/// #[inject_fields(Base, Auditable)]
+/// pub struct Record;
+/// ```
+///
+/// An enum gets the injected fields in each struct-like variant, unit variants becoming
+/// struct-like, except in variants marked `#[inject(skip)]`, which tuple variants must be. A
+/// variant's own field marked `#[inject(override)]` replaces the injected one, and the enum gets
+/// a getter and a `_mut` accessor per field, returning an `Option` if some variants lack it:
+/// ```rust,ignore
+/// #[inject_fields(Envelope)]
+/// pub enum Event {
+///     Created { name: String },
+///     Deleted,
+///     #[inject(skip)]
+///     Ping(u8),
+/// }
+///
+/// assert_eq!(Event::Deleted { id: 1, sent_at }.id(), Some(&1));
+/// ```
+///
+/// `embed` adds a source as a single field instead, named after the source in snake case or
+/// given as `embed = name`. A target embedding exactly one source also derefs to it:
+/// ```rust,ignore
+/// #[inject_fields(Base(embed))]
+/// pub struct Document {
+///     pub title: String,
+/// }
+/// ```
+///
+/// The `conversions` option generates `Target::from_parts`, `Target::into_parts`, a cloning
+/// `Target::to_<source>` and `From<Target>` for each source:
+/// ```rust,ignore
+/// #[inject_fields(Base, Auditable, conversions)]
+/// pub struct Document {
+///     pub title: String,
+/// }
+///
+/// let (base, auditable, title) = Document::from_parts(base, auditable, title).into_parts();
+/// ```
+///
+/// The `builder` option generates `Target::builder()` and a `<Target>Builder` with a setter for
+/// every field. Fields marked `#[inject(default)]` or `#[inject(default = <expr>)]`, in the target
+/// or in a source, may be left unset:
+/// ```rust,ignore
+/// #[inject_fields(Base, builder)]
+/// pub struct Document {
+///     #[inject(default)]
+///     pub tags: Vec<String>,
+/// }
+///
+/// let document = Document::builder().id(1).build()?;
+/// ```
+///
+/// The methods and constants of a source's `#[injectable]` impl block are mixed into the target,
+/// with the source's generic arguments substituted and `self.field` rewritten to the field as
+/// injected. `only(...)` and `except(...)` select them along with the fields:
+/// ```rust,ignore
+/// #[inject_fields(Auditable(except(created_by, touch)))]
+/// pub struct Document {
+///     pub title: String,
+/// }
+/// ```
+///
+/// Attributes and doc comments of source fields are carried over to the injected fields. The
+/// `strip_attrs` option drops all of them, `strip_attrs(...)` drops the listed ones and
+/// `keep_attrs(...)` keeps only the listed ones, matching attributes by path:
+/// ```rust,ignore
+/// #[injectable]
+/// pub struct Base {
+///     /// Unique identifier
+///     #[serde(rename = "ID")]
+///     pub id: u64,
+/// }
+///
+/// #[inject_fields(Base, strip_attrs(serde))]
+/// pub struct Document {
+///     pub title: String,  // `id` keeps its doc comment but not its `serde` attribute
+/// }
+/// ```
+///
 /// # Visibility Rules
 ///
-/// - Private fields cannot be injected across module boundaries
-/// - Public and `pub(crate)` fields maintain their visibility when injected
-/// - The target struct must have access rights to any injected fields
+/// - Every injected field must be visible from the module of the target
+/// - Access is checked by rustc from the target's module, so private, `pub(super)` and
+///   `pub(in path)` fields follow Rust's own module rules
+/// - Relative visibilities are rebased onto the target's module, so injected fields are visible in
+///   the same modules as in their source
+/// - `vis = ...` on a source and `#[inject(vis = ...)]` on a source field replace the visibility of
+///   the injected fields, up to `pub(crate)` for fields that are not `pub` in their source
 ///
 /// # Errors
 ///
 /// This attribute will fail to compile if:
 /// - A source struct is not marked as `#[injectable]`
 /// - There are circular dependencies between structs
-/// - Field names conflict between multiple sources
-/// - Visibility rules are violated
-/// - Applied to an enum or tuple struct
+/// - Field names conflict between multiple sources, after renaming
+/// - A renamed field does not exist in its source
+/// - An injected field is not visible from the module of the target
+/// - A source is named through a `use` import while the visibilities of its fields are relative to
+///   the module defining it
+/// - `vis = pub` is given for a source with fields that are not `pub`
+/// - Applied to a union, or to an enum with a tuple variant not marked `#[inject(skip)]`
+/// - An enum target is given `conversions`, `builder`, or `embed` or `vis` on a source
+/// - A tuple struct is injected into a struct with named fields, or the other way around
+/// - A tuple target is given `conversions`, `builder` or a source option naming its fields
 /// - Generic type parameters are not fully specified
+/// - Generic arguments do not satisfy the bounds of the source's parameters
+/// - A mixed-in method uses a field the target does not inject, is mixed in by two sources, or is
+///   mixed into an enum
+///
+/// Errors point at the offending source, option or field, and all problems found in the
+/// injection are reported together. Misspelled options and field names come with suggestions; a
+/// misspelled source is reported by rustc as a missing macro, since each source resolves through
+/// the hidden macro `#[injectable]` generates under its name, and rustc suggests the injectable
+/// struct with the closest name as a similarly named macro.
 #[proc_macro_attribute]
 pub fn inject_fields(attr:TokenStream, item:TokenStream) -> TokenStream {
   let config = parse_macro_input!(attr as InjectConfig);
   let mut input = parse_macro_input!(item as DeriveInput);
-  let target_name = input.ident.to_string();
-  let mut errors = Vec::new();
 
-  // Collect all validation errors
-  if let Err(err) = validate_and_process_input(&mut input, &config.structs) {
-    errors.push(err.0);
+  // Collect all validation errors, checking the sources and the target's field options regardless
+  // of the target's validation
+  let mut diagnostics = Diagnostics::default();
+  diagnostics.take(validate_and_process_input(&mut input));
+  diagnostics.take(registry::check_target_options(&config, &input));
+  for source in &config.structs {
+    diagnostics.take(check_self_injection(&input.ident, &source.path));
+    diagnostics.take(registry::check_misspelled_option(source));
+  }
+  diagnostics.take(registry::field_options(&input));
+  if let Err(err) = diagnostics.finish() {
+    return TokenStream::from(err.to_compile_error());
   }
 
-  // Check dependencies and update injection chains regardless of validation
-  for type_path in &config.structs {
-    let struct_name = type_path.path.segments.last().unwrap().ident.to_string();
-    if let Err(err) = check_and_update_injection_chain(&target_name, &struct_name) {
-      errors.push(err);
-    }
+  expand_injection(InjectionState {
+    config,
+    target:input,
+    sources:Vec::new(),
+    mixins:Vec::new(),
+  })
+}
+
+/// Continues an injection started by [`macro@inject_fields`].
+///
+/// Invoked by the callback macros generated by [`macro@injectable`], each of which appends the
+/// definition of its struct to the injection state. Not meant to be used directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __inject_fields_expand(input:TokenStream) -> TokenStream {
+  let state = parse_macro_input!(input as InjectionState);
+  expand_injection(state)
+}
+
+/// Resolves the next source of an injection, or injects the fields once all are resolved.
+fn expand_injection(state:InjectionState) -> TokenStream {
+  if let Some(call) = next_source_call(&state) {
+    return TokenStream::from(call);
   }
 
-  // If we have validation errors, return them
-  if !errors.is_empty() {
-    return errors
-      .into_iter()
-      .map(|e| compile_error(&e))
-      .fold(TokenStream::new(), |mut acc, err| {
-        acc.extend(std::iter::once(err));
-        acc
-      });
+  match inject(state) {
+    Ok(tokens) => TokenStream::from(tokens),
+    Err(err) => TokenStream::from(err.to_compile_error()),
   }
+}
 
-  // Update module paths and continue with processing
-  if let Err(err) = update_module_paths(&config.structs) {
-    return compile_error(&err.0);
+/// Injects the fields of all resolved sources into the target.
+///
+/// Errors are reported at the sources listed in `#[inject_fields(...)]`, at the fields named in
+/// their options or at the target's own fields, since the call site is the callback macro of the
+/// last source.
+fn inject(mut state:InjectionState) -> syn::Result<proc_macro2::TokenStream> {
+  let mut diagnostics = Diagnostics::default();
+  let mut sources:Vec<_> = state
+    .config
+    .structs
+    .iter()
+    .zip(&state.sources)
+    .filter_map(|(injection, source)| {
+      diagnostics.take(
+        module_info_from_source(source, &injection.path).map_err(|err| syn::Error::new(injection.path.span(), err.0)),
+      )
+    })
+    .collect();
+  diagnostics.finish()?;
+  for (source, mixin) in sources.iter_mut().zip(&state.mixins) {
+    if let Some(mixin) = mixin {
+      source.methods = mixins::item_names(mixin);
+    }
   }
 
-  // Process the rest as before...
-  let fields = match &mut input.data {
+  // Own fields marked as overrides replace the injected fields of the same name
+  let own_options = registry::field_options(&state.target)?;
+  let (own_fields, overrides, injected, shared) = match &mut state.target.data {
     syn::Data::Struct(data) => {
-      match &mut data.fields {
-        syn::Fields::Named(fields) => fields,
-        _ => return compile_error("Only named fields are supported"),
-      }
+      let own_fields:Vec<_> = data.fields.iter().cloned().collect();
+      let overrides:HashSet<String> = own_fields
+        .iter()
+        .zip(&own_options)
+        .filter(|(_, options)| options.overrides)
+        .filter_map(|(field, _)| field.ident.as_ref().map(ToString::to_string))
+        .collect();
+      let injected = match &mut data.fields {
+        syn::Fields::Named(fields) => registry::process_type_paths(&state.config, &sources, fields, &overrides)?,
+        syn::Fields::Unnamed(fields) => registry::process_positional_fields(&state.config, &sources, fields)?,
+        syn::Fields::Unit => unreachable!("unit targets are given named fields by inject_fields"),
+      };
+      (own_fields, overrides, injected, None)
+    }
+    syn::Data::Enum(data) => {
+      let (injected, shared) = variants::inject_variants(&state.config, &sources, data)?;
+      (Vec::new(), HashSet::new(), injected, Some(shared))
     }
-    _ => return compile_error("Only structs are supported as injection targets"),
+    syn::Data::Union(_) => unreachable!("injection targets are validated by inject_fields"),
   };
 
-  let registry_clone = registry::FIELD_REGISTRY.lock().unwrap();
-  let chains = registry::INJECTION_CHAINS.lock().unwrap();
-  let chains_clone = chains.clone();
-  drop(chains);
+  registry::merge_source_bounds(&state.config, &sources, &mut state.target.generics);
+  let input = &state.target;
+  let deref = registry::embedded_deref_impls(&state.config, input);
+  let access_checks = visibility::access_checks(&state.config, &sources, &injected, input);
+  let module_checks = visibility::module_checks(&state.config, &sources, &injected, &state.mixins);
+  // Enums get accessors to the fields shared by their variants in place of the accessor traits
+  let (field_set_impls, field_sets) = match &shared {
+    Some(shared) => (variants::variant_accessors(input, shared), Vec::new()),
+    None => accessors::field_set_impls(&state.config, &sources, input, &overrides),
+  };
+  let mut diagnostics = Diagnostics::default();
+  let conversions = if state.config.conversions {
+    diagnostics.take(conversions::conversion_impls(
+      &state.config,
+      &injected,
+      &own_fields,
+      input,
+    ))
+  } else {
+    None
+  };
+  let mixins = diagnostics.take(mixins::mixin_impls(
+    &state.config,
+    &sources,
+    &injected,
+    &state.mixins,
+    input,
+  ));
+  let builder = if state.config.builder {
+    diagnostics
+      .take(registry::field_options(input))
+      .map(|options| builder::builder_impls(input, &options))
+  } else {
+    None
+  };
+  diagnostics.finish()?;
 
-  match registry::process_type_paths(config.structs, fields, &registry_clone, &chains_clone) {
-    Ok(_) => TokenStream::from(quote!(#input)),
-    Err(e) => compile_error(&e),
+  // A target that is injectable in turn hands the accessor traits and views it provides over to
+  // its callback, and field options are left for the macros still to expand
+  let mut target = state.target;
+  if target.attrs.iter().any(registry::is_injectable_attr) {
+    target.attrs.extend(field_sets.iter().map(accessors::field_set_attr));
+  }
+  if !target
+    .attrs
+    .iter()
+    .any(|attr| registry::is_injectable_attr(attr) || registry::is_inject_fields_attr(attr))
+  {
+    registry::strip_field_options(&mut target);
   }
+  Ok(quote!(#target #deref #access_checks #module_checks #conversions #field_set_impls #mixins #builder))
 }
@@ -0,0 +1,745 @@
+//! Inherent methods mixed into injection targets.
+//!
+//! `#[injectable] impl Auditable { ... }` registers the impl block with the callback of
+//! `Auditable`, which is marked `#[injectable(methods)]`. Every target injecting `Auditable` then
+//! gets the methods and associated constants of the block in an impl of its own, with:
+//! - the generic parameters of the source replaced by the arguments of the injection
+//! - `self.field` rewritten to the field the target has it injected as, or reached through the
+//!   embedded source
+//! - relative visibilities rebased onto the module of the target, like those of injected fields
+//!
+//! `only(...)` and `except(...)` select methods and constants as well as fields. Methods see the
+//! target as `Self`, whose fields differ in each target, so they cannot construct or destructure
+//! it, and they cannot use a field the target does not inject. Since they expand in the modules of
+//! the targets, they cannot name the items or imports of the struct's module either, only
+//! `crate::` paths, portable names and what they bind or import themselves.
+
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+  parse::Parser,
+  parse_quote,
+  punctuated::Punctuated,
+  spanned::Spanned,
+  visit::{self, Visit},
+  visit_mut::{self, VisitMut},
+};
+
+use crate::{
+  error::Diagnostics,
+  generics::GenericSubstitution,
+  registry::{describe, dollar_crate, ends_with_path_separator, methods_macro_ident, PORTABLE_NAMES},
+  types::{InjectConfig, InjectSource, InjectedFields, ModuleInfo},
+  visibility::{kind_to_visibility, rebase_visibility, visibility_to_kind},
+};
+
+/// Registers an `#[injectable]` impl block with the callback of its struct.
+///
+/// The impl block is kept as it is, and handed to the struct's callback with `@methods`. The
+/// `crate::` paths in the copy are written `$crate::`, so that they resolve to the struct's crate
+/// wherever the methods are mixed in.
+///
+/// # Arguments
+///
+/// * `attr` - The arguments of `#[injectable(...)]`, which impl blocks do not take
+/// * `mixin` - The impl block
+///
+/// # Returns
+///
+/// * `Ok(TokenStream)` with the impl block and its registration
+/// * `Err(syn::Error)` combining every problem with the impl block
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // `#[injectable] impl Auditable { pub fn touch(&mut self) { ... } }` expands to the impl
+/// // block itself, followed by:
+/// __injectables_Auditable_methods! { @methods $ [impl Auditable { pub fn touch(&mut self) { ... } }] }
+/// ```
+pub fn register_mixin(attr:TokenStream, mixin:&syn::ItemImpl) -> syn::Result<TokenStream> {
+  let mut diagnostics = Diagnostics::default();
+  if !attr.is_empty() {
+    diagnostics.push(syn::Error::new_spanned(
+      &attr,
+      "#[injectable] on an impl block takes no options; they go on the struct",
+    ));
+  }
+  diagnostics.take(check_mixin(mixin));
+  diagnostics.finish()?;
+
+  let syn::Type::Path(self_ty) = &*mixin.self_ty else {
+    unreachable!("the self type of mixins is checked by check_mixin");
+  };
+  let mut callback = self_ty.path.clone();
+  for segment in &mut callback.segments {
+    segment.arguments = syn::PathArguments::None;
+  }
+  let last = callback.segments.last_mut().unwrap();
+  last.ident = methods_macro_ident(&last.ident);
+
+  let tokens = dollar_crate(mixin.to_token_stream());
+  Ok(quote! {
+    #mixin
+    #callback! { @methods $ [#tokens] }
+  })
+}
+
+/// Checks that an impl block can be mixed into the targets of its struct.
+///
+/// # Returns
+///
+/// * `Ok(())` if the impl block can be mixed in
+/// * `Err(syn::Error)` at a trait being implemented, at a self type that does not forward the
+///   parameters of the impl block in order, at any item other than a method or constant, at each
+///   construction or destructuring of `Self`, and at each name that would resolve through the
+///   struct's module
+fn check_mixin(mixin:&syn::ItemImpl) -> syn::Result<()> {
+  let mut diagnostics = Diagnostics::default();
+  if let Some((_, path, _)) = &mixin.trait_ {
+    diagnostics.push(syn::Error::new_spanned(
+      path,
+      "Only inherent impl blocks can be mixed into targets",
+    ));
+  }
+
+  match &*mixin.self_ty {
+    syn::Type::Path(syn::TypePath { qself: None, path }) => {
+      if !forwards_params(path, &mixin.generics) {
+        let ident = &path.segments.last().unwrap().ident;
+        diagnostics.push(syn::Error::new_spanned(
+          path,
+          format!(
+            "The impl block of {} must be generic over all of its parameters, in order, as in `impl<T> {}<T>`",
+            ident, ident
+          ),
+        ));
+      }
+    }
+    ty => {
+      diagnostics.push(syn::Error::new_spanned(
+        ty,
+        "Expected the injectable struct the impl block is for",
+      ))
+    }
+  }
+
+  let mut uses = SelfUses::default();
+  for item in &mixin.items {
+    match item {
+      syn::ImplItem::Fn(_) | syn::ImplItem::Const(_) => uses.visit_impl_item(item),
+      item => {
+        diagnostics.push(syn::Error::new_spanned(
+          item,
+          "Only methods and associated constants can be mixed into targets",
+        ))
+      }
+    }
+  }
+  diagnostics.take(uses.diagnostics.finish());
+
+  // Methods are expanded in the modules of the targets, out of reach of the struct's module
+  let ident = match &*mixin.self_ty {
+    syn::Type::Path(ty) => ty.path.segments.last().map(|segment| &segment.ident),
+    _ => None,
+  };
+  if let Some(ident) = ident {
+    let items = mixin
+      .items
+      .iter()
+      .filter(|item| matches!(item, syn::ImplItem::Fn(_) | syn::ImplItem::Const(_)));
+    for item in items {
+      let mut bindings = Bindings::default();
+      bindings.visit_generics(&mixin.generics);
+      bindings.visit_impl_item(item);
+      let mut names = ModuleNames {
+        source:     ident,
+        bindings:   bindings.names,
+        diagnostics:Diagnostics::default(),
+      };
+      names.visit_impl_item(item);
+      diagnostics.take(names.diagnostics.finish());
+    }
+  }
+
+  diagnostics.finish()
+}
+
+/// Checks whether the arguments of a self type name the parameters of its impl block, in order.
+fn forwards_params(path:&syn::Path, generics:&syn::Generics) -> bool {
+  let args:Vec<_> = match &path.segments.last().unwrap().arguments {
+    syn::PathArguments::None => Vec::new(),
+    syn::PathArguments::AngleBracketed(args) => args.args.iter().collect(),
+    syn::PathArguments::Parenthesized(_) => return false,
+  };
+
+  args.len() == generics.params.len()
+    && args.iter().zip(&generics.params).all(|(arg, param)| {
+      match (arg, param) {
+        (syn::GenericArgument::Lifetime(lifetime), syn::GenericParam::Lifetime(param)) => lifetime == &param.lifetime,
+        (syn::GenericArgument::Type(syn::Type::Path(ty)), syn::GenericParam::Type(param)) => {
+          ty.qself.is_none() && ty.path.is_ident(&param.ident)
+        }
+        (syn::GenericArgument::Type(syn::Type::Path(ty)), syn::GenericParam::Const(param)) => {
+          ty.qself.is_none() && ty.path.is_ident(&param.ident)
+        }
+        _ => false,
+      }
+    })
+}
+
+/// Collects the constructions and destructurings of `Self` in the items of an impl block.
+#[derive(Default)]
+struct SelfUses {
+  diagnostics:Diagnostics,
+}
+
+impl SelfUses {
+  fn construction(&mut self, span:Span) {
+    self.diagnostics.push(syn::Error::new(
+      span,
+      "Methods mixed into targets cannot construct Self, whose fields differ in each target",
+    ));
+  }
+
+  fn destructuring(&mut self, span:Span) {
+    self.diagnostics.push(syn::Error::new(
+      span,
+      "Methods mixed into targets cannot destructure Self, whose fields differ in each target; access them \
+       through self",
+    ));
+  }
+
+  /// Finds `Self { .. }` and `Self(..)` in the tokens of a macro invocation.
+  fn visit_tokens(&mut self, tokens:TokenStream) {
+    let tokens:Vec<_> = tokens.into_iter().collect();
+    for (index, token) in tokens.iter().enumerate() {
+      match token {
+        TokenTree::Ident(ident) if ident == "Self" => {
+          if let Some(TokenTree::Group(group)) = tokens.get(index + 1) {
+            if matches!(group.delimiter(), Delimiter::Brace | Delimiter::Parenthesis) {
+              self.construction(ident.span());
+            }
+          }
+        }
+        TokenTree::Group(group) => self.visit_tokens(group.stream()),
+        _ => {}
+      }
+    }
+  }
+}
+
+impl<'ast> Visit<'ast> for SelfUses {
+  fn visit_expr_struct(&mut self, expr:&'ast syn::ExprStruct) {
+    if expr.path.is_ident("Self") {
+      self.construction(expr.path.span());
+    }
+    visit::visit_expr_struct(self, expr);
+  }
+
+  // Covers `Self(..)` and a unit `Self` alike
+  fn visit_expr_path(&mut self, expr:&'ast syn::ExprPath) {
+    if expr.qself.is_none() && expr.path.is_ident("Self") {
+      self.construction(expr.path.span());
+    }
+    visit::visit_expr_path(self, expr);
+  }
+
+  fn visit_pat_struct(&mut self, pat:&'ast syn::PatStruct) {
+    if pat.path.is_ident("Self") {
+      self.destructuring(pat.path.span());
+    }
+    visit::visit_pat_struct(self, pat);
+  }
+
+  fn visit_pat_tuple_struct(&mut self, pat:&'ast syn::PatTupleStruct) {
+    if pat.path.is_ident("Self") {
+      self.destructuring(pat.path.span());
+    }
+    visit::visit_pat_tuple_struct(self, pat);
+  }
+
+  fn visit_macro(&mut self, mac:&'ast syn::Macro) { self.visit_tokens(mac.tokens.clone()); }
+}
+
+/// Macros of the standard library that resolve in any module.
+const STD_MACROS:&[&str] = &[
+  "assert",
+  "assert_eq",
+  "assert_ne",
+  "debug_assert",
+  "debug_assert_eq",
+  "debug_assert_ne",
+  "format",
+  "format_args",
+  "print",
+  "println",
+  "eprint",
+  "eprintln",
+  "write",
+  "writeln",
+  "vec",
+  "panic",
+  "todo",
+  "unimplemented",
+  "unreachable",
+  "matches",
+  "concat",
+  "stringify",
+  "dbg",
+  "line",
+  "file",
+  "column",
+  "env",
+  "option_env",
+  "cfg",
+  "compile_error",
+];
+
+/// Collects the names an item binds itself: parameters, patterns, generic parameters, and the
+/// items and imports declared in its body.
+#[derive(Default)]
+struct Bindings {
+  names:HashSet<String>,
+}
+
+impl<'ast> Visit<'ast> for Bindings {
+  fn visit_pat_ident(&mut self, pat:&'ast syn::PatIdent) {
+    self.names.insert(pat.ident.to_string());
+    visit::visit_pat_ident(self, pat);
+  }
+
+  fn visit_type_param(&mut self, param:&'ast syn::TypeParam) {
+    self.names.insert(param.ident.to_string());
+    visit::visit_type_param(self, param);
+  }
+
+  fn visit_const_param(&mut self, param:&'ast syn::ConstParam) {
+    self.names.insert(param.ident.to_string());
+    visit::visit_const_param(self, param);
+  }
+
+  fn visit_item(&mut self, item:&'ast syn::Item) {
+    let ident = match item {
+      syn::Item::Const(item) => Some(&item.ident),
+      syn::Item::Enum(item) => Some(&item.ident),
+      syn::Item::Fn(item) => Some(&item.sig.ident),
+      syn::Item::Static(item) => Some(&item.ident),
+      syn::Item::Struct(item) => Some(&item.ident),
+      syn::Item::Trait(item) => Some(&item.ident),
+      syn::Item::Type(item) => Some(&item.ident),
+      syn::Item::Union(item) => Some(&item.ident),
+      _ => None,
+    };
+    if let Some(ident) = ident {
+      self.names.insert(ident.to_string());
+    }
+    visit::visit_item(self, item);
+  }
+
+  fn visit_use_name(&mut self, name:&'ast syn::UseName) { self.names.insert(name.ident.to_string()); }
+
+  fn visit_use_rename(&mut self, rename:&'ast syn::UseRename) { self.names.insert(rename.rename.to_string()); }
+}
+
+/// Collects the paths in an item of an impl block that would resolve through the module of its
+/// struct: those starting with a name the item does not bind itself, which is neither portable
+/// nor `self`, `Self` or `crate`.
+struct ModuleNames<'a> {
+  source:     &'a syn::Ident,
+  bindings:   HashSet<String>,
+  diagnostics:Diagnostics,
+}
+
+impl ModuleNames<'_> {
+  fn check(&mut self, path:&syn::Path, known:&[&str]) {
+    if path.leading_colon.is_some() {
+      return;
+    }
+    let ident = &path.segments[0].ident;
+    let name = ident.to_string();
+    if matches!(name.as_str(), "self" | "Self" | "crate")
+      || known.contains(&name.as_str())
+      || self.bindings.contains(&name)
+    {
+      return;
+    }
+    self.diagnostics.push(syn::Error::new(
+      ident.span(),
+      format!(
+        "Methods mixed into targets cannot name '{}' through the module of {}, since they expand in the modules of \
+         the targets; name it through a `crate::` path, or import it in the method",
+        name, self.source
+      ),
+    ));
+  }
+
+  fn check_qualified(&mut self, qself:Option<&syn::QSelf>, path:&syn::Path) {
+    // `<T>::item` starts at a type, and `<T as Trait>::item` at the trait's path
+    if qself.is_none_or(|qself| qself.position > 0) {
+      self.check(path, PORTABLE_NAMES);
+    }
+  }
+}
+
+impl<'ast> Visit<'ast> for ModuleNames<'_> {
+  fn visit_expr_path(&mut self, expr:&'ast syn::ExprPath) {
+    self.check_qualified(expr.qself.as_ref(), &expr.path);
+    visit::visit_expr_path(self, expr);
+  }
+
+  fn visit_type_path(&mut self, ty:&'ast syn::TypePath) {
+    self.check_qualified(ty.qself.as_ref(), &ty.path);
+    visit::visit_type_path(self, ty);
+  }
+
+  fn visit_expr_struct(&mut self, expr:&'ast syn::ExprStruct) {
+    self.check_qualified(expr.qself.as_ref(), &expr.path);
+    visit::visit_expr_struct(self, expr);
+  }
+
+  fn visit_pat_struct(&mut self, pat:&'ast syn::PatStruct) {
+    self.check_qualified(pat.qself.as_ref(), &pat.path);
+    visit::visit_pat_struct(self, pat);
+  }
+
+  fn visit_pat_tuple_struct(&mut self, pat:&'ast syn::PatTupleStruct) {
+    self.check_qualified(pat.qself.as_ref(), &pat.path);
+    visit::visit_pat_tuple_struct(self, pat);
+  }
+
+  fn visit_trait_bound(&mut self, bound:&'ast syn::TraitBound) {
+    self.check(&bound.path, PORTABLE_NAMES);
+    visit::visit_trait_bound(self, bound);
+  }
+
+  // Arguments of macros are checked as far as they parse as expressions
+  fn visit_macro(&mut self, mac:&'ast syn::Macro) {
+    self.check(&mac.path, STD_MACROS);
+    let parser = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+    if let Ok(args) = parser.parse2(mac.tokens.clone()) {
+      for arg in &args {
+        self.visit_expr(arg);
+      }
+    }
+  }
+
+  // Attributes and imports do not name items the way the body does
+  fn visit_attribute(&mut self, _:&'ast syn::Attribute) {}
+
+  fn visit_item_use(&mut self, _:&'ast syn::ItemUse) {}
+}
+
+/// Returns the names of the methods and constants of an impl block.
+pub fn item_names(mixin:&syn::ItemImpl) -> Vec<String> {
+  mixin
+    .items
+    .iter()
+    .filter_map(item_ident)
+    .map(ToString::to_string)
+    .collect()
+}
+
+/// Returns the name of a method or constant.
+pub fn item_ident(item:&syn::ImplItem) -> Option<&syn::Ident> {
+  match item {
+    syn::ImplItem::Fn(item) => Some(&item.sig.ident),
+    syn::ImplItem::Const(item) => Some(&item.ident),
+    _ => None,
+  }
+}
+
+/// Generates the impl blocks mixing the methods of the sources into a target.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source struct, in the same order as `config.structs`
+/// * `injected` - The fields each source added to the target, in the same order
+/// * `mixins` - The `#[injectable]` impl block of each source, if it has one, in the same order
+/// * `target` - The target, with its fields injected
+///
+/// # Returns
+///
+/// * `Ok(TokenStream)` with an inherent impl of the target for each source with selected methods
+/// * `Err(syn::Error)` at the source for each method using a field the target does not inject,
+///   each method mixed in by another source as well, and any method selected for an enum
+pub fn mixin_impls(
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
+  injected:&[InjectedFields],
+  mixins:&[Option<syn::ItemImpl>],
+  target:&syn::DeriveInput,
+) -> syn::Result<TokenStream> {
+  let mut diagnostics = Diagnostics::default();
+  let mut impls = TokenStream::new();
+  let mut mixed:HashMap<String, String> = HashMap::new();
+
+  for (((injection, source), fields), mixin) in config.structs.iter().zip(sources).zip(injected).zip(mixins) {
+    let Some(mixin) = mixin else {
+      continue;
+    };
+    let items:Vec<_> = mixin
+      .items
+      .iter()
+      .filter(|item| item_ident(item).is_some_and(|ident| injection.options.includes(&ident.to_string())))
+      .collect();
+    if items.is_empty() {
+      continue;
+    }
+    let names:Vec<_> = items
+      .iter()
+      .filter_map(|item| item_ident(item))
+      .map(ToString::to_string)
+      .collect();
+
+    // The fields of an enum are spread over its variants, out of reach of `self.field`
+    if let syn::Data::Enum(_) = target.data {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "Cannot mix the methods of {} into enum {}; exclude them with except({})",
+          describe(&injection.path),
+          target.ident,
+          names.join(", ")
+        ),
+      ));
+      continue;
+    }
+
+    for name in &names {
+      if let Some(previous) = mixed.insert(name.clone(), describe(&injection.path)) {
+        diagnostics.push(syn::Error::new(
+          injection.path.span(),
+          format!(
+            "'{}' is mixed into {} by both {} and {}; exclude it from one of them with except({})",
+            name,
+            target.ident,
+            previous,
+            describe(&injection.path),
+            name
+          ),
+        ));
+      }
+    }
+
+    if let Some(tokens) = diagnostics.take(mixin_impl(injection, source, fields, mixin, &items, target)) {
+      impls.extend(tokens);
+    }
+  }
+
+  diagnostics.finish()?;
+  Ok(impls)
+}
+
+/// Generates the impl block mixing the selected items of one source's impl block into a target.
+fn mixin_impl(
+  injection:&InjectSource,
+  source:&ModuleInfo,
+  injected:&InjectedFields,
+  mixin:&syn::ItemImpl,
+  items:&[&syn::ImplItem],
+  target:&syn::DeriveInput,
+) -> syn::Result<TokenStream> {
+  // The impl block forwards the source's parameters in order, so it shares their defaults
+  let mut generics = mixin.generics.clone();
+  for (param, source_param) in generics.params.iter_mut().zip(&source.generics.params) {
+    match (param, source_param) {
+      (syn::GenericParam::Type(param), syn::GenericParam::Type(source_param)) => {
+        param.default = source_param.default.clone();
+      }
+      (syn::GenericParam::Const(param), syn::GenericParam::Const(source_param)) => {
+        param.default = source_param.default.clone();
+      }
+      _ => {}
+    }
+  }
+  let substitution = GenericSubstitution::new(&generics, &injection.path.path.segments.last().unwrap().arguments);
+
+  let mut access = FieldAccess {
+    fields:     source.fields.iter().map(|field| field.name.as_str()).collect(),
+    members:    injected
+      .fields
+      .iter()
+      .chain(&injected.shared)
+      .map(|(from, to)| (from.to_token_stream().to_string(), to))
+      .collect(),
+    embedded:   injected.embedded.as_ref(),
+    unavailable:Vec::new(),
+  };
+  let mut diagnostics = Diagnostics::default();
+  let items:Vec<_> = items
+    .iter()
+    .map(|item| {
+      let mut item = (*item).clone();
+      (&substitution).visit_impl_item_mut(&mut item);
+      access.visit_impl_item_mut(&mut item);
+      for field in std::mem::take(&mut access.unavailable) {
+        let name = item_ident(&item).unwrap();
+        diagnostics.push(syn::Error::new(
+          injection.path.span(),
+          format!(
+            "Method '{}' of {} uses field '{}', which is not injected into {}; exclude the method with except({})",
+            name,
+            describe(&injection.path),
+            field,
+            target.ident,
+            name
+          ),
+        ));
+      }
+
+      // Relative visibilities are written from the source's module, like those of its fields
+      let vis = match &mut item {
+        syn::ImplItem::Fn(item) => &mut item.vis,
+        syn::ImplItem::Const(item) => &mut item.vis,
+        _ => unreachable!("mixins hold methods and constants only"),
+      };
+      *vis = kind_to_visibility(&rebase_visibility(
+        &visibility_to_kind(vis),
+        source.module_path.as_ref(),
+      ));
+      item
+    })
+    .collect();
+  diagnostics.finish()?;
+
+  let mut impl_generics = target.generics.clone();
+  if substitution.covers(&generics) {
+    impl_generics
+      .make_where_clause()
+      .predicates
+      .extend(substitution.predicates(&generics));
+  }
+  let (impl_generics, ty_generics, where_clause) = impl_generics.split_for_impl();
+  let ident = &target.ident;
+  let attrs = &mixin.attrs;
+
+  Ok(quote! {
+    #(#attrs)*
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #(#items)*
+    }
+  })
+}
+
+/// Rewrites the accesses of mixed-in methods to the fields of their source.
+///
+/// # Fields
+///
+/// * `fields` - Names of the fields of the source, positional ones by their index
+/// * `members` - The field of the target each injected field of the source became, by name
+/// * `embedded` - Name of the field the source is embedded as, if it is not flattened
+/// * `unavailable` - Fields of the source used by the current item but not injected
+struct FieldAccess<'a> {
+  fields:     HashSet<&'a str>,
+  members:    HashMap<String, &'a syn::Member>,
+  embedded:   Option<&'a syn::Ident>,
+  unavailable:Vec<String>,
+}
+
+impl FieldAccess<'_> {
+  /// Returns the access to a field of the source in the target, or `None` for any other member.
+  fn resolve(&mut self, member:&syn::Member) -> Option<TokenStream> {
+    let name = member.to_token_stream().to_string();
+    if !self.fields.contains(name.as_str()) {
+      return None;
+    }
+
+    if let Some(embedded) = self.embedded {
+      return Some(quote!(#embedded.#member));
+    }
+    match self.members.get(&name) {
+      Some(target) => Some(respan(target, member.span()).into_token_stream()),
+      None => {
+        if !self.unavailable.contains(&name) {
+          self.unavailable.push(name);
+        }
+        None
+      }
+    }
+  }
+
+  /// Rewrites `self.field` in the tokens of a macro invocation, leaving method calls alone.
+  fn rewrite_tokens(&mut self, tokens:TokenStream) -> TokenStream {
+    let tokens:Vec<_> = tokens.into_iter().collect();
+    let mut rewritten = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+      if let [TokenTree::Ident(receiver), TokenTree::Punct(dot), member, ..] = &tokens[index..] {
+        let is_call =
+          matches!(tokens.get(index + 3), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis);
+        let member = match member {
+          TokenTree::Ident(ident) => Some(syn::Member::Named(ident.clone())),
+          TokenTree::Literal(literal) => {
+            syn::parse_str::<syn::Index>(&literal.to_string()).ok().map(|index| {
+              syn::Member::Unnamed(syn::Index {
+                index:index.index,
+                span: literal.span(),
+              })
+            })
+          }
+          _ => None,
+        };
+        if let Some(member) = member
+          .filter(|_| receiver == "self" && dot.as_char() == '.' && !is_call && !ends_with_path_separator(&rewritten))
+        {
+          if let Some(access) = self.resolve(&member) {
+            rewritten.push(tokens[index].clone());
+            rewritten.push(tokens[index + 1].clone());
+            rewritten.extend(access);
+            index += 3;
+            continue;
+          }
+        }
+      }
+
+      rewritten.push(match &tokens[index] {
+        TokenTree::Group(group) => {
+          let mut rewritten_group = proc_macro2::Group::new(group.delimiter(), self.rewrite_tokens(group.stream()));
+          rewritten_group.set_span(group.span());
+          TokenTree::Group(rewritten_group)
+        }
+        token => token.clone(),
+      });
+      index += 1;
+    }
+    rewritten.into_iter().collect()
+  }
+}
+
+impl VisitMut for FieldAccess<'_> {
+  fn visit_expr_field_mut(&mut self, expr:&mut syn::ExprField) {
+    let is_self = matches!(&*expr.base, syn::Expr::Path(base) if base.qself.is_none() && base.path.is_ident("self"));
+    if is_self {
+      if let Some(access) = self.resolve(&expr.member) {
+        let base = &expr.base;
+        *expr = parse_quote!(#base.#access);
+        return;
+      }
+    }
+    visit_mut::visit_expr_field_mut(self, expr);
+  }
+
+  fn visit_macro_mut(&mut self, mac:&mut syn::Macro) {
+    mac.tokens = self.rewrite_tokens(std::mem::take(&mut mac.tokens));
+  }
+}
+
+/// Returns a member with another span, so that errors about it point at the mixed-in method.
+fn respan(member:&syn::Member, span:Span) -> syn::Member {
+  match member {
+    syn::Member::Named(ident) => {
+      let mut ident = ident.clone();
+      ident.set_span(span);
+      syn::Member::Named(ident)
+    }
+    syn::Member::Unnamed(index) => {
+      syn::Member::Unnamed(syn::Index {
+        index:index.index,
+        span,
+      })
+    }
+  }
+}
@@ -1,143 +1,798 @@
-//! Module for managing injectable field registries and dependency chains.
+//! Module for resolving injectable field sets and validating dependency chains.
 //!
 //! This module provides the core functionality for:
-//! - Tracking injectable fields and their properties
-//! - Managing dependency relationships between structs
+//! - Publishing the fields of injectable structs through callback macros
+//! - Validating dependency relationships between structs
 //! - Validating field visibility and accessibility
 //! - Processing field injections while respecting Rust's type system
 //!
 //! # Implementation Details
 //!
-//! The module maintains two global registries:
+//! Field sets travel between the two attributes as tokens; no state is kept between macro
+//! invocations, so every expansion depends only on its input and can be cached and replayed:
 //!
-//! 1. Field Registry (`FIELD_REGISTRY`):
-//!    - Maps struct names to their field definitions and module information
-//!    - Populated when processing `#[injectable]` attributes
-//!    - Consulted during field injection for field information
+//! 1. Callback macros:
+//!    - `#[injectable]` emits a hidden `macro_rules!` carrying the struct definition, re-exported
+//!      under the struct's own name so it resolves wherever the struct does
+//!    - `#[inject_fields]` expands through the callback macro of each source in turn, each of
+//!      which hands its definition to `__inject_fields_expand!` together with the pending state
+//!    - Since rustc resolves macro paths regardless of item order, injection does not depend on
+//!      the order in which structs are declared or expanded
 //!
-//! 2. Injection Chain Registry (`INJECTION_CHAINS`):
-//!    - Tracks dependency relationships between structs
-//!    - Used to detect circular dependencies
-//!    - Maps target structs to their dependencies (direct and transitive)
-//!    - Ensures valid injection chains during compilation
+//! 2. Dependency chains:
+//!    - A struct that is both `#[injectable]` and `#[inject_fields]` publishes its callback macro
+//!      only once its own injection has completed, so every callback carries the complete field
+//!      list, including transitively injected fields
+//!    - A struct injecting from itself is rejected outright; on a longer cycle, no struct ever
+//!      completes its injection, so rustc reports the sources on the cycle as unresolved
+//!    - Every published field is marked with the struct declaring it, so a field arriving through
+//!      several sources, as in a diamond, is injected once, while unrelated fields of the same
+//!      name and different instantiations of a shared ancestor are reported
 
 use std::{
-  collections::{HashMap, HashSet, VecDeque},
-  sync::Mutex,
+  collections::{HashMap, HashSet},
+  hash::{DefaultHasher, Hash, Hasher},
+  sync::atomic::{AtomicU64, Ordering},
 };
 
-use lazy_static::lazy_static;
-use proc_macro2::Span;
-use quote::ToTokens;
-use syn::Field;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{spanned::Spanned, Field};
 
 use crate::{
-  types::{FieldDef, FieldTypeInfo, InjectionError, ModuleInfo},
-  visibility::{can_access_field, kind_to_visibility},
+  accessors::{field_set_attr, is_field_set_attr, own_field_set},
+  error::{did_you_mean, Diagnostics},
+  generics::GenericSubstitution,
+  types::{
+    AttrFilter, FieldDef, FieldOptions, FieldOrigin, FieldSet, FieldSetKind, FieldTypeInfo, InjectConfig, InjectSource,
+    InjectableConfig, InjectedFields, InjectionError, InjectionState, ModuleInfo, StructShape, INJECT_FIELDS_OPTIONS,
+  },
+  visibility::{
+    depends_on_module, kind_to_visibility, rebase_visibility, visibility_to_kind, widens_beyond_crate, ModulePath,
+  },
 };
 
-lazy_static! {
-  pub static ref FIELD_REGISTRY: Mutex<HashMap<String, ModuleInfo>> = Mutex::new(HashMap::new());
+/// Checks that an injection does not name the target itself as its source.
+///
+/// Only a path that unambiguously refers to the target is rejected: the target's own name,
+/// optionally prefixed with `self::`. Longer cycles never complete, since every struct on them
+/// waits for the callback macro of the next, and rustc reports their sources as unresolved.
+///
+/// # Arguments
+///
+/// * `target` - Name of the struct receiving injected fields
+/// * `source` - Source path as written in `#[inject_fields(...)]`
+///
+/// # Returns
+///
+/// * `Ok(())` if the source is not the target itself
+/// * `Err(syn::Error)` at the source if the target injects from itself
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// # use syn::parse_quote;
+/// # use crate::registry::check_self_injection;
+/// let target:syn::Ident = parse_quote!(Target);
+/// assert!(check_self_injection(&target, &parse_quote!(Source)).is_ok());
+/// assert!(check_self_injection(&target, &parse_quote!(self::Target)).is_err());
+/// ```
+pub fn check_self_injection(target:&syn::Ident, source:&syn::TypePath) -> syn::Result<()> {
+  let segments = &source.path.segments;
+  let names_target = match segments.len() {
+    1 => source.path.leading_colon.is_none(),
+    2 => segments[0].ident == "self",
+    _ => false,
+  };
+
+  if names_target && source.qself.is_none() && segments.last().unwrap().ident == *target {
+    return Err(syn::Error::new(
+      source.span(),
+      format!("Recursive injection detected: {} tries to inject from itself", target),
+    ));
+  }
+
+  Ok(())
+}
+
+/// Checks that a source listed in `#[inject_fields(...)]` is not a misspelled option.
+///
+/// A misspelled option such as `conversion` parses as a source path, and would otherwise be
+/// reported by rustc as an unresolved macro. Struct names are written in upper camel case, so
+/// only lowercase names close to an option are taken for one.
+///
+/// # Returns
+///
+/// * `Ok(())` if the source is not a misspelled option
+/// * `Err(syn::Error)` at the source, suggesting the option
+pub fn check_misspelled_option(source:&InjectSource) -> syn::Result<()> {
+  let Some(ident) = source.path.path.get_ident() else {
+    return Ok(());
+  };
+  let name = ident.to_string();
+  if !name.starts_with(|c:char| c.is_lowercase()) || !source.options.is_empty() {
+    return Ok(());
+  }
+
+  match did_you_mean(&name, INJECT_FIELDS_OPTIONS.iter().copied()) {
+    Some(option) => {
+      Err(syn::Error::new(
+        ident.span(),
+        format!("Unknown option '{}'; did you mean '{}'?", name, option),
+      ))
+    }
+    None => Ok(()),
+  }
 }
 
-lazy_static! {
-  pub static ref INJECTION_CHAINS: Mutex<HashMap<String, HashSet<String>>> = Mutex::new(HashMap::new());
+/// Checks whether an attribute is an `#[inject_fields(...)]` attribute, however it was imported.
+pub fn is_inject_fields_attr(attr:&syn::Attribute) -> bool {
+  attr
+    .path()
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == "inject_fields")
 }
 
-/// Validates and updates the injection dependency chain for a new injection.
+/// Checks whether an attribute is an `#[injectable(...)]` attribute, however it was imported.
+pub fn is_injectable_attr(attr:&syn::Attribute) -> bool {
+  attr
+    .path()
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == "injectable")
+}
+
+/// Collects the options given with `#[inject(...)]` to each field of a struct.
 ///
-/// This function checks if adding an injection from `source` to `target` would
-/// create a circular dependency, and if not, updates the dependency chain.
+/// # Returns
+///
+/// * `Ok(Vec<FieldOptions>)` with the options of each field, in declaration order
+/// * `Err(syn::Error)` pointing at the first malformed option, or at a `vis` option making a field
+///   that is not `pub` public
+pub fn field_options(input:&syn::DeriveInput) -> syn::Result<Vec<FieldOptions>> {
+  match &input.data {
+    syn::Data::Struct(data) => {
+      data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+          let options = FieldOptions::from_attrs(&field.attrs)?;
+          match &options.vis {
+            Some(vis) if widens_beyond_crate(&visibility_to_kind(&field.vis), vis) => {
+              Err(widening_error(vis, &[field_name(field, index)]))
+            }
+            _ => Ok(options),
+          }
+        })
+        .collect()
+    }
+    _ => Ok(Vec::new()),
+  }
+}
+
+/// Returns the name of a field, or its index if it is positional.
+fn field_name(field:&Field, index:usize) -> String {
+  field
+    .ident
+    .as_ref()
+    .map_or_else(|| index.to_string(), ToString::to_string)
+}
+
+/// Returns the identifier of a field, or `_<index>` if it is positional, for the hidden items
+/// named after fields.
+fn field_ident(field:&Field, index:usize) -> syn::Ident {
+  field.ident.clone().unwrap_or_else(|| format_ident!("_{}", index))
+}
+
+/// Removes the `#[inject(...)]` attributes and origin markers from the fields of a struct, once no
+/// macro is left to read them.
+pub fn strip_field_options(input:&mut syn::DeriveInput) {
+  if let syn::Data::Struct(data) = &mut input.data {
+    for field in data.fields.iter_mut() {
+      field
+        .attrs
+        .retain(|attr| !FieldOptions::is_options_attr(attr) && !is_origin_attr(attr));
+    }
+  }
+}
+
+/// Name of the hidden attribute marking each published field with its [`FieldOrigin`].
+const ORIGIN_ATTR:&str = "__injectables_origin";
+
+/// Checks whether an attribute marks the origin of a field.
+pub fn is_origin_attr(attr:&syn::Attribute) -> bool { attr.path().is_ident(ORIGIN_ATTR) }
+
+/// Creates the attribute marking the origin of a field.
+fn origin_attr(origin:&FieldOrigin) -> syn::Attribute {
+  let ident = format_ident!("{}", ORIGIN_ATTR);
+  syn::parse_quote!(#[#ident(#origin)])
+}
+
+/// Counts the injectable structs expanded so far by this process.
+static EXPANSIONS:AtomicU64 = AtomicU64::new(0);
+
+/// Hashes the definition site of an injectable struct, which tells apart same-named structs.
+///
+/// Structs written inside a `macro_rules!` body share the span of that body across invocations,
+/// so the hash also covers a count of the expansions so far. It is therefore only stable within
+/// one expansion, which is all its uses need: the callback macro and the field origins derived
+/// from it travel together in the struct's expansion.
+fn definition_site(ident:&syn::Ident) -> u64 {
+  let span = ident.span().unwrap();
+  let expansion = EXPANSIONS.fetch_add(1, Ordering::Relaxed);
+  let mut hasher = DefaultHasher::new();
+  (span.file(), span.line(), span.column(), expansion).hash(&mut hasher);
+  hasher.finish()
+}
+
+/// Returns the name of the hidden callback macro generated for an injectable struct.
+///
+/// The name includes the hash of the struct's definition site, so the callbacks of same-named
+/// injectables in nested modules never shadow one another.
+fn callback_macro_ident(ident:&syn::Ident, site:u64) -> syn::Ident {
+  format_ident!("__injectables_{}_{:016x}", ident, site)
+}
+
+/// Returns the name of the hidden module holding the field type aliases of an exported struct.
+fn field_alias_module_ident(ident:&syn::Ident) -> syn::Ident { format_ident!("__injectables_{}_fields", ident) }
+
+/// Generates the callback macro that publishes the definition of an injectable struct.
+///
+/// The macro appends the struct definition to the state it is invoked with and hands the result
+/// to `__inject_fields_expand!`. It is re-exported under the struct's own name; macros live in
+/// their own namespace, so the alias resolves wherever the struct does without clashing with it.
+///
+/// A struct with `methods` is not published until its `#[injectable]` impl block registers with
+/// `@methods`: the callback then defines the macro appending both the definition and the impl
+/// block, re-exported under the struct's name in its place. For any other struct, registering an
+/// impl block is an error.
+///
+/// An exported struct gets a `#[macro_export]` callback re-exported with `pub` visibility, and
+/// the types of its public fields are published through the aliases of [`exported_definition`].
+///
+/// Next to the callback, a hidden constant holds the struct's module path, against which targets
+/// check the module they name the struct through (see
+/// [`module_checks`](crate::visibility::module_checks)).
 ///
 /// # Arguments
 ///
-/// * `target` - Name of the struct receiving injected fields
-/// * `source` - Name of the struct providing fields for injection
+/// * `input` - The injectable struct, with all of its fields already in place
+/// * `config` - The options given to `#[injectable(...)]`
+/// * `field_sets` - Hidden attributes carrying the accessor traits and views the struct provides
+///   for its own sources, if it injects any
 ///
 /// # Returns
 ///
-/// * `Ok(())` if the injection is valid and the chain was updated
-/// * `Err(String)` with an error message if the injection would create a circular dependency
+/// The callback macro definition and its re-exports
 ///
 /// # Examples
 ///
 /// ```rust,ignore
-/// # use crate::registry::check_and_update_injection_chain;
-/// let result = check_and_update_injection_chain("Target", "Source");
-/// assert!(result.is_ok());
+/// // `#[injectable] pub struct Base { pub id: u64 }` additionally expands to:
+/// pub(crate) const __injectables_Base_module: &str = ::core::module_path!();
+/// macro_rules! __injectables_Base_9a3f27c10b6e4d85 {
+///   (@methods $($mixin:tt)*) => {
+///     ::core::compile_error!("Add `methods` to #[injectable] on Base to mix ...");
+///   };
+///   ($($state:tt)*) => {
+///     ::injectables::__inject_fields_expand! { $($state)* { pub struct Base { pub id: u64 } } }
+///   };
+/// }
+/// pub(crate) use __injectables_Base_9a3f27c10b6e4d85 as Base;
+/// pub(crate) use __injectables_Base_9a3f27c10b6e4d85 as __injectables_Base_methods;
+/// ```
+pub fn injectable_callback(
+  input:&syn::DeriveInput,
+  config:&InjectableConfig,
+  field_sets:Vec<syn::Attribute>,
+) -> TokenStream {
+  let name = &input.ident;
+  let site = definition_site(name);
+  let callback = callback_macro_ident(name, site);
+  let (aliases, mut definition) = if config.export {
+    exported_definition(input)
+  } else {
+    (TokenStream::new(), input.clone())
+  };
+
+  // Fields declared by the struct itself originate here, injected ones keep their origin
+  if let syn::Data::Struct(data) = &mut definition.data {
+    for (index, field) in data.fields.iter_mut().enumerate() {
+      if !field.attrs.iter().any(is_origin_attr) {
+        let origin = FieldOrigin::new(name, &field_ident(field, index), site);
+        field.attrs.push(origin_attr(&origin));
+      }
+    }
+  }
+
+  // Accessor traits and views travel with the definition, the struct's own ones first
+  let own_kinds = [
+    (config.accessors, FieldSetKind::Accessors),
+    (config.views, FieldSetKind::View),
+  ];
+  for (_, kind) in own_kinds.into_iter().filter(|(enabled, _)| *enabled) {
+    let own = field_set_attr(&own_field_set(&definition, kind));
+    definition.attrs.push(own);
+  }
+  if config.export {
+    // Paths the struct resolved from its own crate root must resolve there from other crates too
+    definition.attrs.extend(field_sets.into_iter().map(|mut attr| {
+      if let syn::Meta::List(list) = &mut attr.meta {
+        list.tokens = dollar_crate(std::mem::take(&mut list.tokens));
+      }
+      attr
+    }));
+  } else {
+    definition.attrs.extend(field_sets);
+  }
+
+  let (export, vis) = if config.export {
+    (quote!(#[macro_export]), quote!(pub))
+  } else {
+    (TokenStream::new(), quote!(pub(crate)))
+  };
+  let methods = methods_macro_ident(name);
+  let marker = module_marker_ident(name);
+  // The module path is read by targets whose visibilities are rebased onto this module
+  let module = quote! {
+    #[doc(hidden)]
+    #[allow(dead_code, non_upper_case_globals)]
+    #vis const #marker: &str = ::core::module_path!();
+  };
+
+  // A struct with methods is published by its impl block, whose callback carries both
+  if config.methods {
+    let mixed = format_ident!("{}_mixed", callback);
+    return quote! {
+      #aliases
+      #module
+
+      #[doc(hidden)]
+      #export
+      macro_rules! #callback {
+        (@methods $d:tt [$($mixin:tt)*]) => {
+          #[doc(hidden)]
+          #export
+          macro_rules! #mixed {
+            ($d($d state:tt)*) => {
+              ::injectables::__inject_fields_expand! { $d($d state)* { #definition } [$($mixin)*] }
+            };
+          }
+
+          #[doc(hidden)]
+          #[allow(unused_imports)]
+          #vis use #mixed as #name;
+        };
+      }
+
+      #[doc(hidden)]
+      #[allow(unused_imports)]
+      #vis use #callback as #methods;
+    };
+  }
+
+  let message = format!(
+    "Add `methods` to #[injectable] on {} to mix the methods of its #[injectable] impl block into its targets",
+    name
+  );
+  quote! {
+    #aliases
+    #module
+
+    #[doc(hidden)]
+    #export
+    macro_rules! #callback {
+      (@methods $($mixin:tt)*) => {
+        ::core::compile_error!(#message);
+      };
+      ($($state:tt)*) => {
+        ::injectables::__inject_fields_expand! { $($state)* { #definition } }
+      };
+    }
+
+    #[doc(hidden)]
+    #[allow(unused_imports)]
+    #vis use #callback as #name;
+    #[doc(hidden)]
+    #[allow(unused_imports)]
+    #vis use #callback as #methods;
+  }
+}
+
+/// Returns the name of the hidden constant holding the module path of an injectable struct.
+pub fn module_marker_ident(ident:&syn::Ident) -> syn::Ident { format_ident!("__injectables_{}_module", ident) }
+
+/// Returns the name under which an injectable struct receives its `#[injectable]` impl block.
+pub fn methods_macro_ident(ident:&syn::Ident) -> syn::Ident { format_ident!("__injectables_{}_methods", ident) }
+
+/// Rewrites the definition of an exported struct so that its field types resolve from any crate.
+///
+/// The type of each public field that names anything beyond the prelude is published as a type
+/// alias in a hidden module next to the struct, and the field's type is replaced by a `self::`
+/// path to that alias. The consumer rebases such paths onto the path it names the source by (see
+/// [`module_info_from_source`]), so `models::Auditable` finds its field types under
+/// `models::__injectables_Auditable_fields`. Non-public fields cannot be injected across crates
+/// and keep their types as written.
+///
+/// # Arguments
+///
+/// * `input` - The exported struct
+///
+/// # Returns
+///
+/// The hidden alias module and the rewritten struct definition
+///
+/// # Examples
 ///
-/// // Trying to create a circular dependency
-/// let err = check_and_update_injection_chain("Source", "Target");
-/// assert!(err.is_err());
+/// ```rust,ignore
+/// // For `pub struct Auditable<T> { pub created_by: UserId, pub tags: Vec<T> }`:
+/// pub mod __injectables_Auditable_fields {
+///   use super::*;
+///   pub type created_by = UserId;
+/// }
+/// // The published definition becomes:
+/// pub struct Auditable<T> { pub created_by: self::__injectables_Auditable_fields::created_by, pub tags: Vec<T> }
 /// ```
-pub fn check_and_update_injection_chain(target:&str, source:&str) -> Result<(), String> {
-  let mut chains = INJECTION_CHAINS.lock().unwrap();
-
-  // Check for direct recursion
-  if source == target {
-    return Err(format!(
-      "Recursive injection detected: {} tries to inject from itself",
-      source
-    ));
+fn exported_definition(input:&syn::DeriveInput) -> (TokenStream, syn::DeriveInput) {
+  let module = field_alias_module_ident(&input.ident);
+  let generic_params:Vec<&syn::GenericParam> = input.generics.params.iter().collect();
+
+  let mut definition = input.clone();
+  let mut aliases = Vec::new();
+  if let syn::Data::Struct(data) = &mut definition.data {
+    for (index, field) in data.fields.iter_mut().enumerate() {
+      if !matches!(field.vis, syn::Visibility::Public(_)) {
+        continue;
+      }
+
+      let tokens = field.ty.to_token_stream();
+      let mut used = Vec::new();
+      if !is_portable_type(tokens, &generic_params, &mut used) {
+        let name = field_ident(field, index);
+        let ty = &field.ty;
+        let params:Vec<_> = generic_params.iter().filter(|param| used.contains(param)).collect();
+        let declarations = params.iter().map(|param| {
+          match param {
+            syn::GenericParam::Lifetime(param) => param.lifetime.to_token_stream(),
+            syn::GenericParam::Type(param) => param.ident.to_token_stream(),
+            syn::GenericParam::Const(param) => {
+              let (ident, ty) = (&param.ident, &param.ty);
+              quote!(const #ident: #ty)
+            }
+          }
+        });
+        let args = params.iter().map(|param| {
+          match param {
+            syn::GenericParam::Lifetime(param) => param.lifetime.to_token_stream(),
+            syn::GenericParam::Type(param) => param.ident.to_token_stream(),
+            syn::GenericParam::Const(param) => param.ident.to_token_stream(),
+          }
+        });
+
+        aliases.push(quote!(pub type #name<#(#declarations),*> = #ty;));
+        field.ty = syn::parse_quote!(self::#module::#name<#(#args),*>);
+      }
+    }
   }
 
-  // Get the target's existing dependencies
-  let target_deps = chains.get(target).cloned().unwrap_or_default();
+  if aliases.is_empty() {
+    return (TokenStream::new(), definition);
+  }
 
-  // Get the source's dependencies
-  let mut source_deps = chains.get(source).cloned().unwrap_or_default();
-  source_deps.insert(source.to_string()); // Include the source itself in its dependencies
+  let module = quote! {
+    #[doc(hidden)]
+    #[allow(non_snake_case, non_camel_case_types, dead_code)]
+    pub mod #module {
+      #[allow(unused_imports)]
+      use super::*;
 
-  // Check if adding this injection would create a cycle
-  if source_deps.iter().any(|dep| target_deps.contains(dep) || dep == target) {
-    return Err(format!(
-      "Circular injection chain detected: {} already depends on {}",
-      target, source
-    ));
+      #(#aliases)*
+    }
+  };
+
+  (module, definition)
+}
+
+/// Names that resolve to the same item from any crate, so types built only from them need no alias.
+/// `alloc` is left out, since it only resolves in crates declaring `extern crate alloc`.
+pub const PORTABLE_NAMES:&[&str] = &[
+  "bool",
+  "char",
+  "str",
+  "u8",
+  "u16",
+  "u32",
+  "u64",
+  "u128",
+  "usize",
+  "i8",
+  "i16",
+  "i32",
+  "i64",
+  "i128",
+  "isize",
+  "f32",
+  "f64",
+  "std",
+  "core",
+  "Option",
+  "Result",
+  "Vec",
+  "String",
+  "Box",
+  "Fn",
+  "FnMut",
+  "FnOnce",
+  "Send",
+  "Sync",
+  "Sized",
+  "Unpin",
+  "Iterator",
+  "dyn",
+  "impl",
+  "mut",
+  "const",
+  "for",
+  "fn",
+  "unsafe",
+  "extern",
+  "Some",
+  "None",
+  "Ok",
+  "Err",
+  "Default",
+  "Clone",
+  "Copy",
+  "PartialEq",
+  "Eq",
+  "PartialOrd",
+  "Ord",
+  "Drop",
+  "From",
+  "Into",
+  "TryFrom",
+  "TryInto",
+  "AsRef",
+  "AsMut",
+  "ToString",
+  "ToOwned",
+  "IntoIterator",
+  "Extend",
+  "DoubleEndedIterator",
+  "ExactSizeIterator",
+  "drop",
+];
+
+/// Checks whether a type names only portable items and generic parameters of its struct.
+///
+/// This works on the tokens of the type: every identifier that starts a path must either be one
+/// of [`PORTABLE_NAMES`] or a generic parameter, while identifiers following `::` are not
+/// considered. The check is conservative; a type that fails it is merely published through an
+/// alias.
+///
+/// # Arguments
+///
+/// * `tokens` - Tokens of the type
+/// * `generic_params` - Generic parameters of the struct
+/// * `used` - Collects the generic parameters that the type mentions, lifetimes included
+///
+/// # Returns
+///
+/// `true` if the type can be written as-is in another crate
+fn is_portable_type<'a>(
+  tokens:TokenStream,
+  generic_params:&[&'a syn::GenericParam],
+  used:&mut Vec<&'a syn::GenericParam>,
+) -> bool {
+  let mut portable = true;
+  let mut continues_path = false;
+  let mut in_lifetime = false;
+
+  for token in tokens {
+    match &token {
+      TokenTree::Group(group) => {
+        portable &= is_portable_type(group.stream(), generic_params, used);
+        continues_path = false;
+      }
+      TokenTree::Punct(punct) => {
+        continues_path = punct.as_char() == ':';
+        in_lifetime = punct.as_char() == '\'';
+      }
+      TokenTree::Ident(ident) => {
+        let param = generic_params.iter().find(|param| {
+          match param {
+            syn::GenericParam::Lifetime(param) => in_lifetime && param.lifetime.ident == *ident,
+            syn::GenericParam::Type(param) => !in_lifetime && param.ident == *ident,
+            syn::GenericParam::Const(param) => !in_lifetime && param.ident == *ident,
+          }
+        });
+
+        if let Some(param) = param.filter(|_| !continues_path) {
+          if !used.contains(param) {
+            used.push(param);
+          }
+        } else if !continues_path && !in_lifetime && !PORTABLE_NAMES.contains(&ident.to_string().as_str()) {
+          portable = false;
+        }
+        continues_path = false;
+        in_lifetime = false;
+      }
+      TokenTree::Literal(_) => continues_path = false,
+    }
   }
 
-  // Update the target's dependencies
-  let target_entry = chains.entry(target.to_string()).or_default();
-  target_entry.insert(source.to_string());
-  target_entry.extend(source_deps);
+  portable
+}
 
-  Ok(())
+/// Emits the invocation of the next unresolved source's callback macro.
+///
+/// Sources are resolved in the order they are listed in `#[inject_fields(...)]`; the number of
+/// definitions already carried by the state tells which one is next.
+///
+/// # Arguments
+///
+/// * `state` - The current resolution state
+///
+/// # Returns
+///
+/// * `Some(TokenStream)` with the callback invocation if a source is still unresolved
+/// * `None` once every source has been resolved
+pub fn next_source_call(state:&InjectionState) -> Option<TokenStream> {
+  let source = state.config.structs.get(state.sources.len())?;
+
+  // The callback macro shares the struct's path, minus any generic arguments
+  let mut callback = source.path.path.clone();
+  for segment in &mut callback.segments {
+    segment.arguments = syn::PathArguments::None;
+  }
+
+  Some(quote!(#callback! { #state }))
 }
 
-/// Extracts the module path from a type path.
+/// Builds the module information of a source struct from the definition carried by its callback.
 ///
 /// # Arguments
 ///
-/// * `type_path` - The type path to process
+/// * `source` - The source struct definition
+/// * `type_path` - The source path as written in `#[inject_fields(...)]`
 ///
 /// # Returns
 ///
-/// A string representation of the module path without the final type name
+/// * `Ok(ModuleInfo)` with the field definitions of the source
+/// * `Err(InjectionError)` if the source is not a struct or the markers on its fields are malformed
+pub fn module_info_from_source(
+  source:&syn::DeriveInput,
+  type_path:&syn::TypePath,
+) -> Result<ModuleInfo, InjectionError> {
+  let generic_params:Vec<String> = source
+    .generics
+    .params
+    .iter()
+    .map(|param| {
+      match param {
+        syn::GenericParam::Lifetime(param) => param.lifetime.to_string(),
+        syn::GenericParam::Type(param) => param.ident.to_string(),
+        syn::GenericParam::Const(param) => param.ident.to_string(),
+      }
+    })
+    .collect();
+
+  let fields = match &source.data {
+    syn::Data::Struct(data) => &data.fields,
+    _ => return Err(InjectionError("Only structs are supported".to_string())),
+  };
+
+  let module_path = ModulePath::of_source(type_path);
+  let field_defs = fields
+    .iter()
+    .enumerate()
+    .map(|(index, f)| {
+      let origin = f
+        .attrs
+        .iter()
+        .find(|attr| is_origin_attr(attr))
+        .ok_or_else(|| InjectionError(format!("Missing origin of field '{}'", field_name(f, index))))?
+        .parse_args()
+        .map_err(|err| InjectionError(format!("Malformed field origin: {}", err)))?;
+      // A visibility given for injection replaces the field's own, both relative to the source
+      let options = FieldOptions::from_attrs(&f.attrs)
+        .map_err(|err| InjectionError(format!("Malformed field options: {}", err)))?;
+      let vis = visibility_to_kind(options.vis.as_ref().unwrap_or(&f.vis));
+      Ok(FieldDef {
+        name:field_name(f, index),
+        ty:rebase_self_path(&f.ty, type_path),
+        vis:rebase_visibility(&vis, module_path.as_ref()),
+        relative:depends_on_module(&vis, module_path.as_ref()),
+        generic_params:generic_params.clone(),
+        attrs:f.attrs.clone(),
+        origin,
+      })
+    })
+    .collect::<Result<_, InjectionError>>()?;
+
+  let field_sets = source
+    .attrs
+    .iter()
+    .filter(|attr| is_field_set_attr(attr))
+    .map(|attr| {
+      let mut set:FieldSet = attr
+        .parse_args()
+        .map_err(|err| InjectionError(format!("Malformed field set: {}", err)))?;
+      set.path = match rebase_self_path(
+        &syn::Type::Path(syn::TypePath {
+          qself:None,
+          path: set.path,
+        }),
+        type_path,
+      ) {
+        syn::Type::Path(rebased) => rebased.path,
+        _ => unreachable!("rebasing keeps a path a path"),
+      };
+      for field in &mut set.fields {
+        field.ty = rebase_self_path(&field.ty, type_path);
+      }
+      Ok(set)
+    })
+    .collect::<Result<_, InjectionError>>()?;
+
+  let vis = visibility_to_kind(&source.vis);
+  Ok(ModuleInfo {
+    shape:StructShape::of(fields),
+    fields:field_defs,
+    generics:source.generics.clone(),
+    vis:rebase_visibility(&vis, module_path.as_ref()),
+    relative:depends_on_module(&vis, module_path.as_ref()),
+    module_path,
+    field_sets,
+    methods:Vec::new(),
+  })
+}
+
+/// Rebases a `self::` type path of a source field onto the path the source is named by.
+///
+/// Within the source, `self` is the source's own module; from the target, that module is reached
+/// through the path written in `#[inject_fields(...)]` minus the struct name. This is how the type
+/// aliases of exported structs are found from other crates.
+///
+/// # Arguments
+///
+/// * `ty` - Type of the source field
+/// * `type_path` - The source path as written in `#[inject_fields(...)]`
+///
+/// # Returns
+///
+/// The type as it must be written in the target's module
 ///
 /// # Examples
 ///
 /// ```rust,ignore
 /// # use syn::parse_quote;
-/// # use crate::registry::get_path_from_type;
-/// let type_path = parse_quote!(crate::models::User);
-/// assert_eq!(get_path_from_type(&type_path), "crate::models");
+/// # use crate::registry::rebase_self_path;
+/// let ty = parse_quote!(self::__injectables_Auditable_fields::created_by);
+/// let rebased = rebase_self_path(&ty, &parse_quote!(models::Auditable));
+/// assert_eq!(rebased, parse_quote!(models::__injectables_Auditable_fields::created_by));
 /// ```
-pub fn get_path_from_type(type_path:&syn::TypePath) -> String {
-  let mut module_path = String::new();
-  let segments = &type_path.path.segments;
-  let segment_count = segments.len();
-
-  for (i, segment) in segments.iter().enumerate() {
-    if i == segment_count - 1 {
-      break;
-    }
-    if !module_path.is_empty() {
-      module_path.push_str("::");
+fn rebase_self_path(ty:&syn::Type, type_path:&syn::TypePath) -> syn::Type {
+  let mut rebased = ty.clone();
+  let prefix = &type_path.path.segments;
+
+  if let syn::Type::Path(syn::TypePath { qself: None, path }) = &mut rebased {
+    if prefix.len() > 1 && path.segments.len() > 1 && path.segments[0].ident == "self" {
+      let mut segments:syn::punctuated::Punctuated<syn::PathSegment, syn::Token![::]> = prefix
+        .iter()
+        .take(prefix.len() - 1)
+        .map(|s| syn::PathSegment::from(s.ident.clone()))
+        .collect();
+      segments.extend(path.segments.iter().skip(1).cloned());
+
+      path.leading_colon = type_path.path.leading_colon;
+      path.segments = segments;
     }
-    module_path.push_str(&segment.ident.to_string());
   }
 
-  module_path
+  rebased
 }
 
 /// Processes type paths for field injection, handling field collection, visibility,
@@ -150,300 +805,901 @@ pub fn get_path_from_type(type_path:&syn::TypePath) -> String {
 /// 4. Handles generic type resolution
 /// 5. Injects fields into the target struct
 ///
+/// Validation carries on past a failing source or field, so that every problem is reported at
+/// once: at the source as written in `#[inject_fields(...)]`, at the field named in its options,
+/// or at the target's own field.
+///
 /// # Arguments
 ///
-/// * `type_paths` - Vector of source struct type paths
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source struct, in the same order as `config.structs`
 /// * `fields` - Named fields of the target struct
-/// * `registry` - Reference to the field registry
-/// * `chains` - Reference to the injection chains
+/// * `overrides` - Names of the target's own fields marked `#[inject(override)]`
 ///
 /// # Returns
 ///
-/// * `Ok(())` if processing succeeds
-/// * `Err(String)` with an error message if any validation fails
+/// * `Ok(Vec<InjectedFields>)` with the fields each source added, in configuration order
+/// * `Err(syn::Error)` combining every validation error
 pub fn process_type_paths(
-  type_paths:Vec<syn::TypePath>,
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
   fields:&mut syn::FieldsNamed,
-  registry:&HashMap<String, ModuleInfo>,
-  chains:&HashMap<String, HashSet<String>>,
-) -> Result<(), String> {
-  let mut added_fields = HashSet::new();
+  overrides:&HashSet<String>,
+) -> syn::Result<Vec<InjectedFields>> {
+  let mut diagnostics = Diagnostics::default();
+  let mut injected = Vec::new();
   let mut field_types:HashMap<String, FieldTypeInfo> = HashMap::new();
 
-  for type_path in type_paths {
-    let last_segment = type_path.path.segments.last().unwrap();
-    let struct_name = last_segment.ident.to_string();
-    let target_module = String::new();
+  // The target's own fields are never injected: those marked as overrides replace the injected
+  // field, the others are reported as duplicates naming the source
+  let declared:HashMap<String, Span> = fields
+    .named
+    .iter()
+    .filter_map(|field| field.ident.as_ref())
+    .map(|ident| (ident.to_string(), ident.span()))
+    .collect();
+  let mut added_fields:HashSet<String> = declared.keys().cloned().collect();
+  let mut overridden = HashSet::new();
 
-    let all_fields = collect_fields(&struct_name, registry, chains)?;
+  // Each combination of source, generic arguments and naming options is a distinct injection, so
+  // a source may be injected several times under different names; listing the same one twice is
+  // a mistake
+  let mut listed = HashSet::new();
+  for injection in &config.structs {
+    if !listed.insert(injection.to_token_stream().to_string()) {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "{} is listed more than once with the same options",
+          describe(&injection.path)
+        ),
+      ));
+    }
+  }
 
-    for field in &all_fields {
-      let ty_str = process_field_type(field, last_segment);
+  for (injection, source) in config.structs.iter().zip(sources) {
+    let last_segment = injection.path.path.segments.last().unwrap();
+    let span = injection.path.span();
 
-      // Check for conflicting field types and visibility
-      if let Some(existing) = field_types.get(&field.name) {
-        if existing.ty != ty_str {
-          return Err(format!(
-            "Conflicting types for field '{}': found both '{}' and '{}'",
-            field.name, existing.ty, ty_str
-          ));
-        }
+    if source.shape == StructShape::Tuple {
+      diagnostics.push(syn::Error::new(
+        span,
+        format!(
+          "Cannot inject tuple struct {} into a struct with named fields; its fields have no names",
+          describe(&injection.path)
+        ),
+      ));
+      injected.push(InjectedFields {
+        embedded:None,
+        fields:  Vec::new(),
+        shared:  Vec::new(),
+        complete:false,
+      });
+      continue;
+    }
 
-        if existing.vis != field.vis {
-          return Err(format!(
-            "Conflicting visibility for field '{}': cannot have both private and public fields with the same name",
-            field.name
-          ));
-        }
+    let options_checked = diagnostics.take(check_field_options(injection, source)).is_some();
+    let vis_checked = diagnostics.take(check_widened_visibility(injection, source)).is_some();
+    if !options_checked || !vis_checked {
+      injected.push(InjectedFields {
+        embedded:None,
+        fields:  Vec::new(),
+        shared:  Vec::new(),
+        complete:false,
+      });
+      continue;
+    }
+
+    if let Some(name) = injection.embedded_name() {
+      if let Some(own) = declared.get(&name.to_string()) {
+        diagnostics.push(if overrides.contains(&name.to_string()) {
+          syn::Error::new(
+            *own,
+            format!(
+              "Cannot override field '{}' embedding {}; embed it under another name with `embed = name`",
+              name, last_segment.ident
+            ),
+          )
+        } else {
+          duplicate_field_error(*own, &name.to_string(), &last_segment.ident)
+        });
+      } else if let Some(existing) = field_types.get(&name.to_string()) {
+        diagnostics.push(syn::Error::new(
+          span,
+          format!(
+            "Conflicting types for field '{}': found both '{}' and '{}'",
+            name,
+            existing.ty,
+            injection.path.to_token_stream()
+          ),
+        ));
       } else {
         field_types.insert(
-          field.name.clone(),
+          name.to_string(),
           FieldTypeInfo {
-            name:field.name.clone(),
-            ty:  ty_str,
-            vis: field.vis.clone(),
+            ty:    injection.path.to_token_stream().to_string(),
+            vis:   injection.options.field_vis(&source.vis),
+            origin:None,
+            source:describe(&injection.path),
           },
         );
+        diagnostics.take(embed_field(source, injection, name.clone(), &mut fields.named));
       }
+      injected.push(InjectedFields {
+        embedded:Some(name),
+        fields:  Vec::new(),
+        shared:  Vec::new(),
+        complete:true,
+      });
+      continue;
     }
 
-    process_fields(
-      &struct_name,
-      all_fields,
-      &mut added_fields,
-      &target_module,
-      registry,
-      last_segment,
-      &mut fields.named,
-    )?;
+    let mut shared_fields = Vec::new();
+    for field in source
+      .fields
+      .iter()
+      .filter(|field| injection.options.includes(&field.name))
+    {
+      let name = injection.options.field_name(&field.name);
+      if let Some(own) = declared.get(&name) {
+        if overrides.contains(&name) {
+          overridden.insert(name);
+        } else {
+          diagnostics.push(duplicate_field_error(*own, &name, &last_segment.ident));
+        }
+        continue;
+      }
+      let ty_str = process_field_type(field, source, last_segment)
+        .to_token_stream()
+        .to_string();
+
+      // Check for conflicting field types and visibility, under the names the fields are injected
+      // as. A field arriving again from the struct declaring it is shared, as in a diamond, and
+      // injected once; a field of the same name declared by another struct is a conflict
+      let Some(existing) = field_types.get(&name) else {
+        field_types.insert(
+          name,
+          FieldTypeInfo {
+            ty:    ty_str,
+            vis:   injection.options.field_vis(&field.vis),
+            origin:Some(field.origin.clone()),
+            source:describe(&injection.path),
+          },
+        );
+        continue;
+      };
+
+      let shared = existing.origin.as_ref() == Some(&field.origin);
+      let message = if existing.ty != ty_str && shared {
+        format!(
+          "Conflicting instantiations of {} for field '{}': '{}' from {} and '{}' from {}; inject them under \
+           distinct names with prefix or rename",
+          field.origin.source,
+          name,
+          existing.ty,
+          existing.source,
+          ty_str,
+          describe(&injection.path)
+        )
+      } else if existing.ty != ty_str {
+        format!(
+          "Conflicting types for field '{}': found both '{}' and '{}'",
+          name, existing.ty, ty_str
+        )
+      } else if existing.vis != injection.options.field_vis(&field.vis) {
+        format!(
+          "Conflicting visibility for field '{}': cannot have both private and public fields with the same name",
+          name
+        )
+      } else if !shared {
+        format!(
+          "Field '{}' is injected from both {} and {}, which declare it separately; inject one under another name \
+           with prefix or rename",
+          name,
+          existing.source,
+          describe(&injection.path)
+        )
+      } else {
+        let span = last_segment.ident.span();
+        shared_fields.push((
+          syn::Member::Named(syn::Ident::new(&field.name, span)),
+          syn::Member::Named(syn::Ident::new(&name, span)),
+        ));
+        continue;
+      };
+      diagnostics.push(syn::Error::new(field_span(injection, &field.name), message));
+    }
+
+    let added = diagnostics
+      .take(process_fields(
+        source,
+        injection,
+        &mut added_fields,
+        &config.attrs,
+        &mut fields.named,
+      ))
+      .unwrap_or_default();
+    injected.push(InjectedFields {
+      embedded:None,
+      complete:added.len() + shared_fields.len() == source.fields.len(),
+      fields:  added,
+      shared:  shared_fields,
+    });
   }
 
-  Ok(())
+  let mut unused:Vec<_> = overrides.difference(&overridden).collect();
+  unused.sort();
+  for name in unused {
+    diagnostics.push(unused_override_error(declared[name], name));
+  }
+
+  diagnostics.finish()?;
+  Ok(injected)
+}
+
+/// Returns the span to report a problem with an injected field at: the field's name in the
+/// source's options if it is renamed there, or else the source as written in
+/// `#[inject_fields(...)]`.
+fn field_span(injection:&InjectSource, field:&str) -> Span {
+  injection
+    .options
+    .renames
+    .iter()
+    .find(|(name, _)| name == field)
+    .map_or_else(|| injection.path.span(), |(_, renamed)| renamed.span())
 }
 
-/// Collects all fields from a struct and its dependencies recursively.
+/// Formats a source path for error messages, without the spacing of its tokens.
+pub fn describe(path:&syn::TypePath) -> String {
+  path
+    .to_token_stream()
+    .to_string()
+    .replace(" :: ", "::")
+    .replace(" < ", "<")
+    .replace(" <", "<")
+    .replace(" >", ">")
+    .replace(" ,", ",")
+}
+
+/// Creates the error for a field of the target that a source injects as well, without the target
+/// marking it as an override, reported at the target's field.
+pub fn duplicate_field_error(span:Span, name:&str, source:&syn::Ident) -> syn::Error {
+  syn::Error::new(
+    span,
+    format!(
+      "Field '{}' is already injected from {}; mark it #[inject(override)] to replace the injected field",
+      name, source
+    ),
+  )
+}
+
+/// Creates the error for a field of the target marked as an override that no source injects,
+/// reported at the target's field.
+pub fn unused_override_error(span:Span, name:&str) -> syn::Error {
+  syn::Error::new(
+    span,
+    format!(
+      "Field '{}' is marked #[inject(override)], but no source injects a field of that name",
+      name
+    ),
+  )
+}
+
+/// Checks that the options of an injection apply to the shape of its target.
 ///
-/// This function performs a breadth-first traversal of the injection dependency
-/// graph to collect all fields that should be injected.
+/// Positional fields cannot be named, embedded, converted or built, and only they can be put
+/// before the target's own fields. Enums are neither converted nor built, and their variants hold
+/// neither embedded sources nor fields with a visibility of their own.
 ///
 /// # Arguments
 ///
-/// * `start_struct` - Name of the starting struct
-/// * `registry` - Reference to the field registry
-/// * `chains` - Reference to the injection chains
+/// * `config` - The injection configuration
+/// * `input` - The target struct or enum
 ///
 /// # Returns
 ///
-/// * `Ok(Vec<FieldDef>)` with collected fields
-/// * `Err(String)` if an error occurs during collection
-fn collect_fields(
-  start_struct:&str,
-  registry:&HashMap<String, ModuleInfo>,
-  chains:&HashMap<String, HashSet<String>>,
-) -> Result<Vec<FieldDef>, String> {
-  let mut all_fields = Vec::new();
-  let mut visited = HashSet::new();
-  let mut queue = VecDeque::new();
-
-  queue.push_back(start_struct.to_string());
-
-  while let Some(current_struct) = queue.pop_front() {
-    if !visited.insert(current_struct.clone()) {
-      continue;
+/// * `Ok(())` if every option applies
+/// * `Err(syn::Error)` at the target for each flag and at each source with an option that does not
+///   apply
+pub fn check_target_options(config:&InjectConfig, input:&syn::DeriveInput) -> syn::Result<()> {
+  let mut diagnostics = Diagnostics::default();
+  let (tuple, kind) = match &input.data {
+    syn::Data::Struct(syn::DataStruct {
+      fields: syn::Fields::Unnamed(_),
+      ..
+    }) => (true, "tuple struct"),
+    syn::Data::Enum(_) => (false, "enum"),
+    _ => (false, "struct"),
+  };
+
+  if kind != "struct" {
+    for (enabled, option) in [(config.conversions, "conversions"), (config.builder, "builder")] {
+      if enabled {
+        diagnostics.push(syn::Error::new(
+          input.ident.span(),
+          format!(
+            "Option '{}' requires a struct with named fields, but {} is {} {}",
+            option,
+            input.ident,
+            if tuple { "a" } else { "an" },
+            kind
+          ),
+        ));
+      }
+    }
+  }
+  for injection in &config.structs {
+    let options = &injection.options;
+    let option = if tuple {
+      options.naming_option()
+    } else if kind == "enum" {
+      options
+        .embed
+        .then_some("embed")
+        .or(options.vis.as_ref().map(|_| "vis"))
+        .or(options.prepend.then_some("prepend"))
+    } else {
+      options.prepend.then_some("prepend")
+    };
+    if let Some(option) = option {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "Option '{}' does not apply to {} as a source of {} {}",
+          option,
+          describe(&injection.path),
+          kind,
+          input.ident
+        ),
+      ));
+    }
+  }
+
+  diagnostics.finish()
+}
+
+/// Checks that a `vis` option on a source does not make public what is not public in the source.
+///
+/// # Arguments
+///
+/// * `injection` - The source as listed in `#[inject_fields(...)]`, with its options
+/// * `source` - Module information of the source struct
+///
+/// # Returns
+///
+/// * `Ok(())` if the option keeps every field within its bounds
+/// * `Err(syn::Error)` at the `vis` option, naming the fields it would widen
+fn check_widened_visibility(injection:&InjectSource, source:&ModuleInfo) -> syn::Result<()> {
+  let Some(vis) = &injection.options.vis else {
+    return Ok(());
+  };
+
+  let widened:Vec<String> = if injection.options.embed {
+    let name = injection.embedded_name().unwrap().to_string();
+    widens_beyond_crate(&source.vis, vis)
+      .then_some(name)
+      .into_iter()
+      .collect()
+  } else {
+    source
+      .fields
+      .iter()
+      .filter(|field| injection.options.includes(&field.name) && widens_beyond_crate(&field.vis, vis))
+      .map(|field| field.name.clone())
+      .collect()
+  };
+  if widened.is_empty() {
+    return Ok(());
+  }
+  Err(widening_error(vis, &widened))
+}
+
+/// Creates the error for a `vis` option making fields public that are not public in their
+/// source, reported at the option.
+fn widening_error(vis:&syn::Visibility, fields:&[String]) -> syn::Error {
+  let fields:Vec<_> = fields.iter().map(|field| format!("'{}'", field)).collect();
+  syn::Error::new(
+    vis.span(),
+    format!(
+      "Cannot inject {} as pub: {} not pub in {} source; injected fields can be widened up to pub(crate)",
+      fields.join(", "),
+      if fields.len() == 1 { "it is" } else { "they are" },
+      if fields.len() == 1 { "its" } else { "their" },
+    ),
+  )
+}
+
+/// Checks that the fields named in the options of a source exist in that source, or for `only` and
+/// `except`, that they are fields or mixed-in methods of it.
+///
+/// # Arguments
+///
+/// * `injection` - The source as listed in `#[inject_fields(...)]`, with its options
+/// * `source` - Module information of the source struct
+///
+/// # Returns
+///
+/// * `Ok(())` if every field named in the options exists
+/// * `Err(syn::Error)` at each unknown field, suggesting the closest field of the source or
+///   listing the available ones
+fn check_field_options(injection:&InjectSource, source:&ModuleInfo) -> syn::Result<()> {
+  let mut diagnostics = Diagnostics::default();
+  let renamed = injection.options.renames.iter().map(|(field, _)| ("rename", field));
+  let selected = injection.options.selected_fields().map(|field| ("select", field));
+  let fields:Vec<&str> = source.fields.iter().map(|def| def.name.as_str()).collect();
+  // Methods mixed into targets are selected along with the fields
+  let selectable:Vec<&str> = fields
+    .iter()
+    .copied()
+    .chain(source.methods.iter().map(String::as_str))
+    .collect();
+
+  for (action, field) in renamed.chain(selected) {
+    let (available, kind, kinds) = if action == "select" && !source.methods.is_empty() {
+      (&selectable, "field or method", "fields and methods")
+    } else {
+      (&fields, "field", "fields")
+    };
+    if !available.iter().any(|name| field == name) {
+      let hint = match did_you_mean(&field.to_string(), available.iter().copied()) {
+        Some(candidate) => format!("did you mean '{}'?", candidate),
+        None => format!("available {}: {}", kinds, available.join(", ")),
+      };
+      diagnostics.push(syn::Error::new(
+        field.span(),
+        format!(
+          "Cannot {} unknown {} '{}' of {}; {}",
+          action,
+          kind,
+          field,
+          injection.path.path.segments.last().unwrap().ident,
+          hint
+        ),
+      ));
     }
+  }
 
-    if let Some(info) = registry.get(&current_struct) {
-      all_fields.extend(info.fields.clone());
+  diagnostics.finish()
+}
+
+/// Merges the bounds of each source's generic parameters into the target's where-clause.
+///
+/// A target forwarding its own parameters to a source, as in
+/// `#[inject_fields(Container<T>)] struct Wrapper<T>`, needs the source's bounds on them for the
+/// injected field types to be well-formed. Bounds on concrete arguments are merged as well, so
+/// that rustc reports an argument that does not satisfy them. Sources instantiated without an
+/// argument for every parameter are skipped, as their bounds cannot be expressed for the target.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source struct, in the same order as `config.structs`
+/// * `generics` - Generics of the target struct
+pub fn merge_source_bounds(config:&InjectConfig, sources:&[ModuleInfo], generics:&mut syn::Generics) {
+  for (injection, source) in config.structs.iter().zip(sources) {
+    let last_segment = injection.path.path.segments.last().unwrap();
+    let substitution = GenericSubstitution::new(&source.generics, &last_segment.arguments);
+    if !substitution.covers(&source.generics) {
+      continue;
     }
 
-    if let Some(deps) = chains.get(&current_struct) {
-      for dep in deps {
-        if !visited.contains(dep) {
-          queue.push_back(dep.clone());
-        }
+    for predicate in substitution.predicates(&source.generics) {
+      let where_clause = generics.make_where_clause();
+      let exists = where_clause
+        .predicates
+        .iter()
+        .any(|existing| existing.to_token_stream().to_string() == predicate.to_token_stream().to_string());
+      if !exists {
+        where_clause.predicates.push(predicate);
       }
     }
   }
-
-  Ok(all_fields)
 }
 
 /// Processes fields for injection, handling field creation and visibility.
 ///
-/// This function creates new fields in the target struct based on the collected
-/// field definitions, respecting visibility rules and handling type resolution.
+/// This function creates new fields in the target struct based on the field definitions
+/// of a source struct, respecting visibility rules and handling type resolution.
 ///
 /// # Arguments
 ///
-/// * `struct_name` - Name of the current struct being processed
-/// * `all_fields` - Vector of field definitions to process
+/// * `source` - Module information of the source struct being processed
+/// * `injection` - The source as listed in `#[inject_fields(...)]`, with its options
 /// * `added_fields` - Set of field names already added
-/// * `target_module` - Module path of the target struct
-/// * `registry` - Reference to the field registry
-/// * `last_segment` - Last segment of the type path
+/// * `attr_filter` - Which attributes of the source fields to carry over
 /// * `named_fields` - Named fields of the target struct
 ///
 /// # Returns
 ///
-/// * `Ok(())` if processing succeeds
-/// * `Err(String)` with an error message if processing fails
+/// * `Ok(Vec<(Member, Member)>)` with the name in the source and in the target of each added field
+/// * `Err(syn::Error)` if the options of a field are malformed
 fn process_fields(
-  struct_name:&str,
-  all_fields:Vec<FieldDef>,
+  source:&ModuleInfo,
+  injection:&InjectSource,
   added_fields:&mut HashSet<String>,
-  target_module:&str,
-  registry:&HashMap<String, ModuleInfo>,
-  last_segment:&syn::PathSegment,
+  attr_filter:&AttrFilter,
   named_fields:&mut syn::punctuated::Punctuated<Field, syn::Token![,]>,
-) -> Result<(), String> {
-  for field in all_fields {
-    if !added_fields.insert(field.name.clone()) {
+) -> syn::Result<Vec<(syn::Member, syn::Member)>> {
+  let mut added = Vec::new();
+  let last_segment = injection.path.path.segments.last().unwrap();
+
+  for field in source
+    .fields
+    .iter()
+    .filter(|field| injection.options.includes(&field.name))
+  {
+    let name = injection.options.field_name(&field.name);
+    if !added_fields.insert(name.clone()) {
       continue;
     }
 
-    let field_info = FieldTypeInfo {
-      name:field.name.clone(),
-      ty:  process_field_type(&field, last_segment),
-      vis: field.vis.clone(),
-    };
+    let span = last_segment.ident.span();
+    let name = syn::Ident::new(&name, span);
+    named_fields.push(injected_field(
+      field,
+      source,
+      injection,
+      attr_filter,
+      Some(name.clone()),
+    )?);
+    added.push((
+      syn::Member::Named(syn::Ident::new(&field.name, span)),
+      syn::Member::Named(name),
+    ));
+  }
+  Ok(added)
+}
+
+/// Creates the field injected into the target for a field of a source.
+///
+/// The field is spanned to the source named in `#[inject_fields(...)]`, so that diagnostics about
+/// it point there rather than into the source's callback macro. It carries over the source field's
+/// docs and the attributes passing the filter, and its options except for the visibility it is
+/// injected with.
+///
+/// # Arguments
+///
+/// * `field` - The field of the source
+/// * `source` - Module information of the source struct
+/// * `injection` - The source as listed in `#[inject_fields(...)]`, with its options
+/// * `attr_filter` - Which attributes of the source fields to carry over
+/// * `ident` - Name of the injected field, `None` for a positional field
+///
+/// # Returns
+///
+/// * `Ok(Field)` with the injected field
+/// * `Err(syn::Error)` if the field's type does not parse once respanned, or its options are
+///   malformed
+fn injected_field(
+  field:&FieldDef,
+  source:&ModuleInfo,
+  injection:&InjectSource,
+  attr_filter:&AttrFilter,
+  ident:Option<syn::Ident>,
+) -> syn::Result<Field> {
+  let last_segment = injection.path.path.segments.last().unwrap();
+  let span = last_segment.ident.span();
+  let ty = process_field_type(field, source, last_segment);
+  let ty:syn::Type = syn::parse2(respan(ty.into_token_stream(), span))?;
+  let vis = injection.options.field_vis(&field.vis);
+  let vis:syn::Visibility = syn::parse2(respan(kind_to_visibility(&vis).to_token_stream(), span))?;
+
+  let mut options = FieldOptions::from_attrs(&field.attrs)?;
+  options.vis = None;
+  Ok(Field {
+    attrs:field
+      .attrs
+      .iter()
+      .filter(|attr| !FieldOptions::is_options_attr(attr) && (attr_filter.retains(attr) || is_origin_attr(attr)))
+      .cloned()
+      .chain(options.to_attr())
+      .collect(),
+    vis,
+    mutability:syn::FieldMutability::None,
+    colon_token:ident.as_ref().map(|_| Default::default()),
+    ident,
+    ty,
+  })
+}
+
+/// Injects the positional fields of tuple sources into a tuple target.
+///
+/// The fields of each source are added in order after the target's own fields or, for sources
+/// given `prepend`, before them; unit sources add nothing. Positional fields have no names to
+/// share or clash, so every injection adds all of its source's fields.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source struct, in the same order as `config.structs`
+/// * `fields` - Positional fields of the target struct
+///
+/// # Returns
+///
+/// * `Ok(Vec<InjectedFields>)` with the fields each source added, in configuration order
+/// * `Err(syn::Error)` at each source with named fields
+pub fn process_positional_fields(
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
+  fields:&mut syn::FieldsUnnamed,
+) -> syn::Result<Vec<InjectedFields>> {
+  let mut diagnostics = Diagnostics::default();
+  let mut prepended = Vec::new();
+  let mut appended = Vec::new();
 
-    if !can_access_field(&field_info.vis, &registry[struct_name].module_path, target_module) {
-      return Err(format!(
-        "Cannot access field '{}' with visibility {:?} from module '{}' in module '{}'",
-        field_info.name, field_info.vis, registry[struct_name].module_path, target_module
+  // The index of each injected field within the fields going before or after the target's own
+  let mut placed = Vec::new();
+  for (injection, source) in config.structs.iter().zip(sources) {
+    if source.shape == StructShape::Named {
+      diagnostics.push(syn::Error::new(
+        injection.path.span(),
+        format!(
+          "Cannot inject named struct {} into a tuple struct; its fields need names",
+          describe(&injection.path)
+        ),
       ));
+      continue;
     }
 
-    let name = syn::Ident::new(&field_info.name, Span::call_site());
-    let ty:syn::Type =
-      syn::parse_str(&field_info.ty).unwrap_or_else(|_| panic!("Failed to parse type: {}", field_info.ty));
-
-    // Create and add the new field
-    let new_field = Field {
-      attrs:vec![],
-      vis:kind_to_visibility(&field_info.vis),
-      mutability:syn::FieldMutability::None,
-      ident:Some(name),
-      colon_token:Some(Default::default()),
-      ty,
+    let span = injection.path.path.segments.last().unwrap().ident.span();
+    let group = if injection.options.prepend {
+      &mut prepended
+    } else {
+      &mut appended
     };
-
-    named_fields.push(new_field);
+    let mut positions = Vec::new();
+    for (index, field) in source.fields.iter().enumerate() {
+      positions.push((
+        syn::Index {
+          index:index as u32,
+          span,
+        },
+        group.len(),
+      ));
+      group.extend(diagnostics.take(injected_field(field, source, injection, &config.attrs, None)));
+    }
+    placed.push((injection.options.prepend, positions));
   }
-  Ok(())
+  diagnostics.finish()?;
+
+  let before = prepended.len();
+  let own = fields.unnamed.len();
+  fields.unnamed = prepended
+    .into_iter()
+    .chain(std::mem::take(&mut fields.unnamed))
+    .chain(appended)
+    .collect();
+
+  Ok(
+    placed
+      .into_iter()
+      .map(|(prepend, positions)| {
+        let fields = positions
+          .into_iter()
+          .map(|(source_index, position)| {
+            let index = if prepend { position } else { before + own + position };
+            let target_index = syn::Index {
+              index:index as u32,
+              span: source_index.span,
+            };
+            (syn::Member::Unnamed(source_index), syn::Member::Unnamed(target_index))
+          })
+          .collect();
+        InjectedFields {
+          embedded:None,
+          fields,
+          shared:Vec::new(),
+          complete:true,
+        }
+      })
+      .collect(),
+  )
 }
 
-/// Processes field type information, handling generic type resolution.
+/// Adds a source to the target as a single field holding the whole source struct.
+///
+/// The field has the source struct's own visibility, so it is accessible wherever the source is.
 ///
 /// # Arguments
 ///
-/// * `field` - Field definition to process
-/// * `last_segment` - Last segment of the type path
+/// * `source` - Module information of the source struct
+/// * `injection` - The source as listed in `#[inject_fields(...)]`
+/// * `name` - Name of the embedded field
+/// * `named_fields` - Named fields of the target struct
 ///
 /// # Returns
 ///
-/// String representation of the resolved type
-fn process_field_type(field:&FieldDef, last_segment:&syn::PathSegment) -> String {
-  if field.generic_params.is_empty() {
-    return field.ty.clone();
-  }
+/// * `Ok(())` once the field is added
+/// * `Err(syn::Error)` if the source path or visibility does not parse once respanned
+fn embed_field(
+  source:&ModuleInfo,
+  injection:&InjectSource,
+  name:syn::Ident,
+  named_fields:&mut syn::punctuated::Punctuated<Field, syn::Token![,]>,
+) -> syn::Result<()> {
+  let span = injection.path.path.segments.last().unwrap().ident.span();
+  let ty:syn::Type = syn::parse2(respan(injection.path.to_token_stream(), span))?;
+  let vis = injection.options.field_vis(&source.vis);
+  let vis:syn::Visibility = syn::parse2(respan(kind_to_visibility(&vis).to_token_stream(), span))?;
 
-  match &last_segment.arguments {
-    syn::PathArguments::AngleBracketed(args) => {
-      let mut ty = field.ty.clone();
-      for (param, arg) in field.generic_params.iter().zip(args.args.iter()) {
-        ty = ty.replace(param, &arg.to_token_stream().to_string());
-      }
-      ty
-    }
-    _ => field.ty.clone(),
-  }
+  named_fields.push(Field {
+    attrs:Vec::new(),
+    vis,
+    mutability:syn::FieldMutability::None,
+    ident:Some(name),
+    colon_token:Some(Default::default()),
+    ty,
+  });
+  Ok(())
 }
 
-/// Validates input and processes injection configuration.
+/// Generates `Deref` and `DerefMut` to the embedded source of a target.
 ///
-/// This function performs initial validation of the target struct and injection sources.
+/// Only a target embedding exactly one source derefs to it; with several, each is reached
+/// through its field.
 ///
 /// # Arguments
 ///
-/// * `input` - The parsed derive input
-/// * `type_paths` - Vector of source struct type paths
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `target` - The target struct, with its fields injected
 ///
 /// # Returns
 ///
-/// * `Ok(())` if validation succeeds
-/// * `Err(InjectionError)` if validation fails
-pub fn validate_and_process_input(
-  input:&mut syn::DeriveInput,
-  type_paths:&[syn::TypePath],
-) -> Result<(), InjectionError> {
-  match &mut input.data {
-    syn::Data::Struct(data) => {
-      match &mut data.fields {
-        syn::Fields::Named(_) => Ok(()),
-        _ => Err(InjectionError("Only named fields are supported".to_string())),
+/// The `Deref` and `DerefMut` impls, or nothing if the target does not embed exactly one source
+pub fn embedded_deref_impls(config:&InjectConfig, target:&syn::DeriveInput) -> TokenStream {
+  let mut embedded = config
+    .structs
+    .iter()
+    .filter_map(|injection| injection.embedded_name().map(|name| (name, &injection.path)));
+  let (Some((field, ty)), None) = (embedded.next(), embedded.next()) else {
+    return TokenStream::new();
+  };
+
+  let ident = &target.ident;
+  let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+  quote! {
+    impl #impl_generics ::core::ops::Deref for #ident #ty_generics #where_clause {
+      type Target = #ty;
+
+      fn deref(&self) -> &Self::Target {
+        &self.#field
       }
     }
-    _ => {
-      Err(InjectionError(
-        "Only structs are supported as injection targets".to_string(),
-      ))
+
+    impl #impl_generics ::core::ops::DerefMut for #ident #ty_generics #where_clause {
+      fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.#field
+      }
     }
-  }?;
+  }
+}
 
-  for type_path in type_paths {
-    let struct_name = type_path.path.segments.last().unwrap().ident.to_string();
+/// Rewrites paths starting with `crate::` to start with `$crate::`, for use in the body of an
+/// exported callback macro, so that they resolve to the crate the tokens come from. A `crate`
+/// that does not start a path, as in `pub(crate)`, is kept.
+pub fn dollar_crate(tokens:TokenStream) -> TokenStream {
+  let tokens:Vec<_> = tokens.into_iter().collect();
+  let mut rewritten = Vec::new();
+  for (index, token) in tokens.iter().enumerate() {
+    match token {
+      TokenTree::Ident(ident) if ident == "crate" && !ends_with_path_separator(&rewritten) => {
+        if starts_with_path_separator(&tokens[index + 1..]) {
+          rewritten.push(TokenTree::Punct(proc_macro2::Punct::new(
+            '$',
+            proc_macro2::Spacing::Alone,
+          )));
+        }
+        rewritten.push(token.clone());
+      }
+      TokenTree::Group(group) => {
+        let mut rewritten_group = Group::new(group.delimiter(), dollar_crate(group.stream()));
+        rewritten_group.set_span(group.span());
+        rewritten.push(TokenTree::Group(rewritten_group));
+      }
+      token => rewritten.push(token.clone()),
+    }
+  }
+  rewritten.into_iter().collect()
+}
 
-    let registry = FIELD_REGISTRY.lock().unwrap();
-    if !registry.contains_key(&struct_name) {
-      return Err(InjectionError(format!(
-        "Cannot inject fields from '{}' as this type does not exist",
-        struct_name
-      )));
+/// Checks whether tokens end with `::`, so that an identifier after them is not a path's start.
+pub fn ends_with_path_separator(tokens:&[TokenTree]) -> bool {
+  match tokens {
+    [.., TokenTree::Punct(first), TokenTree::Punct(second)] => {
+      first.as_char() == ':' && first.spacing() == proc_macro2::Spacing::Joint && second.as_char() == ':'
     }
+    _ => false,
   }
+}
 
-  Ok(())
+/// Checks whether tokens start with `::`.
+fn starts_with_path_separator(tokens:&[TokenTree]) -> bool {
+  match tokens {
+    [TokenTree::Punct(first), TokenTree::Punct(second), ..] => {
+      first.as_char() == ':' && first.spacing() == proc_macro2::Spacing::Joint && second.as_char() == ':'
+    }
+    _ => false,
+  }
 }
 
-/// Updates module paths in the registry based on type paths.
+/// Sets the span of every token in `tokens`, including the contents of groups.
 ///
-/// This function updates the module path information for each injectable struct
-/// based on its usage in injection.
+/// `$crate` keeps its span, which tells the crate it resolves to.
+fn respan(tokens:TokenStream, span:Span) -> TokenStream {
+  tokens
+    .into_iter()
+    .map(|mut token| {
+      match &token {
+        TokenTree::Group(group) => {
+          let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+          respanned.set_span(span);
+          token = TokenTree::Group(respanned);
+        }
+        TokenTree::Ident(ident) if ident == "$crate" => {}
+        _ => token.set_span(span),
+      }
+      token
+    })
+    .collect()
+}
+
+/// Processes field type information, handling generic type resolution.
 ///
 /// # Arguments
 ///
-/// * `type_paths` - Vector of source struct type paths
+/// * `field` - Field definition to process
+/// * `source` - Module information of the source struct declaring the field
+/// * `last_segment` - Last segment of the type path
 ///
 /// # Returns
 ///
-/// * `Ok(())` if update succeeds
-/// * `Err(InjectionError)` if update fails
-///
-/// # Examples
-///
-/// ```rust,ignore
-/// # use syn::parse_quote;
-/// # use crate::registry::update_module_paths;
-/// let type_paths: Vec<syn::TypePath> = vec![parse_quote!(models::User)];
-/// let result = update_module_paths(&type_paths);
-/// ```
-pub fn update_module_paths(type_paths:&[syn::TypePath]) -> Result<(), InjectionError> {
-  let mut registry = FIELD_REGISTRY.lock().unwrap();
+/// The type with the source's generic parameters replaced by the arguments of the injection
+fn process_field_type(field:&FieldDef, source:&ModuleInfo, last_segment:&syn::PathSegment) -> syn::Type {
+  if field.generic_params.is_empty() {
+    return field.ty.clone();
+  }
 
-  for type_path in type_paths {
-    let struct_name = type_path.path.segments.last().unwrap().ident.to_string();
-    let module_path = get_path_from_type(type_path);
+  let substitution = GenericSubstitution::new(&source.generics, &last_segment.arguments);
+  if substitution.is_empty() {
+    return field.ty.clone();
+  }
 
-    if let Some(info) = registry.get_mut(&struct_name) {
-      info.module_path = module_path;
-    } else {
-      return Err(InjectionError(format!(
-        "Cannot inject fields from '{}' as it was not marked as #[injectable] or hasn't been defined yet",
-        struct_name
-      )));
+  substitution.apply(&field.ty)
+}
+
+/// Validates the target of an injection.
+///
+/// This function performs initial validation of the target struct or enum; the sources are validated
+/// by `#[injectable]` and resolved through their callback macros.
+///
+/// # Arguments
+///
+/// * `input` - The parsed derive input
+///
+/// # Returns
+///
+/// * `Ok(())` if validation succeeds
+/// * `Err(syn::Error)` at the fields or the keyword of an unsupported item if validation fails
+pub fn validate_and_process_input(input:&mut syn::DeriveInput) -> syn::Result<()> {
+  match &mut input.data {
+    syn::Data::Struct(data) => {
+      // A unit target becomes a struct with named fields, holding only the injected ones
+      if let syn::Fields::Unit = data.fields {
+        data.fields = syn::Fields::Named(syn::FieldsNamed {
+          brace_token:Default::default(),
+          named:      Default::default(),
+        });
+        data.semi_token = None;
+      }
+      Ok(())
+    }
+    // The variants of an enum are given their fields by `inject_variants`
+    syn::Data::Enum(_) => Ok(()),
+    syn::Data::Union(data) => {
+      Err(syn::Error::new(
+        data.union_token.span,
+        "Only structs and enums are supported as injection targets",
+      ))
     }
   }
-
-  Ok(())
 }
@@ -4,21 +4,87 @@
 //! throughout the crate for managing field injection, including module information,
 //! field definitions, and configuration parsing.
 
-use syn::parse::{Parse, ParseStream};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+  ext::IdentExt,
+  parse::{Parse, ParseStream},
+};
+
+use crate::{
+  error::suggestion,
+  visibility::{visibility_to_kind, VisibilityKind},
+};
+
+/// Options accepted by `#[injectable(...)]`.
+const INJECTABLE_OPTIONS:&[&str] = &["export", "accessors", "views", "methods"];
+
+/// Options accepted after a source in `#[inject_fields(...)]`.
+const SOURCE_OPTIONS:&[&str] = &["embed", "prefix", "rename", "only", "except", "vis", "prepend"];
+
+/// Options accepted by `#[inject(...)]` on a field.
+const FIELD_OPTIONS:&[&str] = &["default", "override", "vis"];
+
+/// Options accepted by `#[inject(...)]` on a variant of an enum target.
+const VARIANT_OPTIONS:&[&str] = &["skip"];
+
+/// Flags and attribute filters accepted by `#[inject_fields(...)]` next to the sources.
+pub const INJECT_FIELDS_OPTIONS:&[&str] = &["conversions", "builder", "strip_attrs", "keep_attrs"];
 
 /// Information about a module and its injectable fields.
 ///
-/// This struct maintains metadata about an injectable struct, including its fields
-/// and module path information.
+/// This struct maintains metadata about an injectable struct, including its fields,
+/// generics and visibility.
 ///
 /// # Fields
 ///
-/// * `fields` - Vector of field definitions from the struct
-/// * `module_path` - Full path to the module containing the struct
+/// * `shape` - Whether the struct has named fields, positional fields or none
+/// * `fields` - Vector of field definitions from the struct; positional fields are named by their
+///   index
+/// * `generics` - Generic parameters of the struct, with their bounds and where-clauses
+/// * `vis` - Visibility of the struct itself, rebased onto the target's module
+/// * `relative` - Whether `vis` depends on the module defining the struct (see
+///   [`depends_on_module`](super::visibility::depends_on_module))
+/// * `module_path` - Module defining the struct, located from the target's module; `None` for a
+///   source named through an extern path
+/// * `field_sets` - Accessor traits and views the struct provides, including those of its own
+///   sources
+/// * `methods` - Names of the methods and constants of the struct's `#[injectable]` impl block
 #[derive(Clone, Debug)]
 pub struct ModuleInfo {
+  pub shape:      StructShape,
   pub fields:     Vec<FieldDef>,
-  pub module_path:String,
+  pub generics:   syn::Generics,
+  pub vis:        super::visibility::VisibilityKind,
+  pub relative:   bool,
+  pub module_path:Option<super::visibility::ModulePath>,
+  pub field_sets: Vec<FieldSet>,
+  pub methods:    Vec<String>,
+}
+
+/// Shape of the fields of a struct.
+///
+/// # Variants
+///
+/// * `Named` - Fields with names, `struct Base { id: u64 }`
+/// * `Tuple` - Positional fields, `struct Pair(u32, u32);`
+/// * `Unit` - No fields, `struct Marker;`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StructShape {
+  Named,
+  Tuple,
+  Unit,
+}
+
+impl StructShape {
+  /// Returns the shape of the given fields.
+  pub fn of(fields:&syn::Fields) -> Self {
+    match fields {
+      syn::Fields::Named(_) => StructShape::Named,
+      syn::Fields::Unnamed(_) => StructShape::Tuple,
+      syn::Fields::Unit => StructShape::Unit,
+    }
+  }
 }
 
 /// Definition of an injectable field.
@@ -29,9 +95,12 @@ pub struct ModuleInfo {
 /// # Fields
 ///
 /// * `name` - Name of the field
-/// * `ty` - Type of the field as a string
-/// * `vis` - Visibility of the field
-/// * `generic_params` - Names of generic type parameters if any
+/// * `ty` - Type of the field
+/// * `vis` - Visibility of the field, rebased onto the target's module
+/// * `relative` - Whether `vis` depends on the module defining the source (see
+///   [`depends_on_module`](super::visibility::depends_on_module))
+/// * `generic_params` - Names of the generic parameters of the source struct (types, lifetimes and consts) if any
+/// * `attrs` - Attributes of the field, including doc comments
 ///
 /// # Examples
 ///
@@ -40,17 +109,23 @@ pub struct ModuleInfo {
 /// # use crate::types::FieldDef;
 /// let field = FieldDef {
 ///     name: "id".to_string(),
-///     ty: "u64".to_string(),
+///     ty: parse_quote!(u64),
 ///     vis: VisibilityKind::Public,
+///     relative: false,
 ///     generic_params: vec![],
+///     attrs: vec![],
+///     origin: FieldOrigin::new(&parse_quote!(Base), &parse_quote!(id), 0),
 /// };
 /// ```
 #[derive(Clone, Debug)]
 pub struct FieldDef {
   pub name:          String,
-  pub ty:            String,
+  pub ty:            syn::Type,
   pub vis:           super::visibility::VisibilityKind,
+  pub relative:      bool,
   pub generic_params:Vec<String>,
+  pub attrs:         Vec<syn::Attribute>,
+  pub origin:        FieldOrigin,
 }
 
 /// Type information for a field during processing.
@@ -60,46 +135,905 @@ pub struct FieldDef {
 ///
 /// # Fields
 ///
-/// * `name` - Name of the field
 /// * `ty` - Type of the field
 /// * `vis` - Visibility of the field
+/// * `origin` - The struct declaring the field, or `None` for the field of an embedded source
+/// * `source` - The source the field is injected from, as written in `#[inject_fields(...)]`
 #[derive(Debug, Clone)]
 pub struct FieldTypeInfo {
-  pub name:String,
-  pub ty:  String,
-  pub vis: super::visibility::VisibilityKind,
+  pub ty:    String,
+  pub vis:   super::visibility::VisibilityKind,
+  pub origin:Option<FieldOrigin>,
+  pub source:String,
+}
+
+/// Configuration of an injectable struct.
+///
+/// Parsed from the attribute arguments of `#[injectable(...)]`.
+///
+/// # Fields
+///
+/// * `export` - Whether the struct can be used as a source by other crates
+/// * `accessors` - Whether a `Has<Name>` trait with accessors for the struct's fields is generated
+/// * `views` - Whether `<Name>Ref` and `<Name>Mut` views of the struct's fields are generated
+/// * `methods` - Whether the struct has an `#[injectable]` impl block whose methods are mixed into
+///   its targets
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // The macro invocation #[injectable(export)]
+/// // would parse into an InjectableConfig with `export` set
+/// ```
+#[derive(Default)]
+pub struct InjectableConfig {
+  pub export:   bool,
+  pub accessors:bool,
+  pub views:    bool,
+  pub methods:  bool,
+}
+
+impl Parse for InjectableConfig {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let mut config = InjectableConfig::default();
+
+    for option in input.parse_terminated(syn::Ident::parse, syn::Token![,])? {
+      match option.to_string().as_str() {
+        "export" => config.export = true,
+        "accessors" => config.accessors = true,
+        "views" => config.views = true,
+        "methods" => config.methods = true,
+        _ => {
+          return Err(syn::Error::new(
+            option.span(),
+            format!(
+              "Unknown injectable option '{}'{}",
+              option,
+              suggestion(&option.to_string(), INJECTABLE_OPTIONS.iter().copied())
+            ),
+          ))
+        }
+      }
+    }
+
+    Ok(config)
+  }
 }
 
 /// Configuration for field injection.
 ///
 /// Parsed from the attribute arguments of `#[inject_fields(...)]`,
-/// containing the source structs to inject fields from.
+/// containing the source structs to inject fields from and the injection options.
 ///
 /// # Fields
 ///
-/// * `structs` - Vector of type paths representing source structs
+/// * `structs` - The source structs, each with its own injection options
+/// * `attrs` - Which attributes of the source fields are carried over to the injected fields
+/// * `conversions` - Whether conversions between the target and its sources are generated
+/// * `builder` - Whether a builder for the target is generated
 ///
 /// # Examples
 ///
 /// ```rust,ignore
-/// // The macro invocation #[inject_fields(UserData, Timestamps)]
-/// // would parse into an InjectConfig containing two TypePaths
+/// // The macro invocation #[inject_fields(UserData, Timestamps(prefix = "ts_"), strip_attrs(serde))]
+/// // would parse into an InjectConfig containing two sources and an attribute filter
 /// ```
 pub struct InjectConfig {
-  pub structs:Vec<syn::TypePath>,
+  pub structs:    Vec<InjectSource>,
+  pub attrs:      AttrFilter,
+  pub conversions:bool,
+  pub builder:    bool,
 }
 
 impl Parse for InjectConfig {
   fn parse(input:ParseStream) -> syn::Result<Self> {
+    let mut structs = Vec::new();
+    let mut attrs = None;
+    let mut conversions = false;
+    let mut builder = false;
+
+    while !input.is_empty() {
+      if peek_flag(input, "conversions") {
+        input.parse::<syn::Ident>()?;
+        conversions = true;
+      } else if peek_flag(input, "builder") {
+        input.parse::<syn::Ident>()?;
+        builder = true;
+      } else if AttrFilter::peek(input) {
+        let span = input.span();
+        if attrs.replace(input.parse()?).is_some() {
+          return Err(syn::Error::new(span, "Only one attribute filter can be given"));
+        }
+      } else {
+        structs.push(input.parse()?);
+      }
+
+      if !input.is_empty() {
+        input.parse::<syn::Token![,]>()?;
+      }
+    }
+
     Ok(InjectConfig {
-      structs:input
-        .parse_terminated(syn::TypePath::parse, syn::Token![,])?
-        .into_iter()
-        .collect(),
+      structs,
+      attrs:attrs.unwrap_or_default(),
+      conversions,
+      builder,
+    })
+  }
+}
+
+impl ToTokens for InjectConfig {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    let structs = &self.structs;
+    let attrs = &self.attrs;
+    let conversions = self.conversions.then(|| quote!(conversions,));
+    let builder = self.builder.then(|| quote!(builder,));
+    tokens.extend(quote!(#(#structs,)* #conversions #builder #attrs));
+  }
+}
+
+/// Checks whether the input continues with the given flag, as opposed to a source path.
+fn peek_flag(input:ParseStream, flag:&str) -> bool {
+  let fork = input.fork();
+  match fork.parse::<syn::Ident>() {
+    Ok(ident) if ident == flag => fork.is_empty() || fork.peek(syn::Token![,]),
+    _ => false,
+  }
+}
+
+/// A source struct listed in `#[inject_fields(...)]`, with its injection options.
+///
+/// Written as the source's type path, optionally followed by its options in parentheses, e.g.
+/// `Address(prefix = "billing_")` or `Base<u64>(rename(id = base_id))`.
+///
+/// # Fields
+///
+/// * `path` - Type path of the source struct, including generic arguments
+/// * `options` - Options controlling how the fields of this source are injected
+pub struct InjectSource {
+  pub path:   syn::TypePath,
+  pub options:SourceOptions,
+}
+
+impl Parse for InjectSource {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    // A source with options reads like a parenthesized path segment, `Fn(A) -> B`, so the path
+    // is parsed one segment at a time
+    let leading_colon:Option<syn::Token![::]> = input.parse()?;
+    let mut segments = syn::punctuated::Punctuated::new();
+    loop {
+      let ident = input.call(syn::Ident::parse_any)?;
+      let arguments = if input.peek(syn::Token![<]) {
+        syn::PathArguments::AngleBracketed(input.parse()?)
+      } else {
+        syn::PathArguments::None
+      };
+      segments.push_value(syn::PathSegment { ident, arguments });
+
+      if !input.peek(syn::Token![::]) {
+        break;
+      }
+      segments.push_punct(input.parse()?);
+    }
+
+    let options = if input.peek(syn::token::Paren) {
+      let content;
+      syn::parenthesized!(content in input);
+      content.parse()?
+    } else {
+      SourceOptions::default()
+    };
+
+    Ok(InjectSource {
+      path:syn::TypePath {
+        qself:None,
+        path: syn::Path {
+          leading_colon,
+          segments,
+        },
+      },
+      options,
+    })
+  }
+}
+
+impl InjectSource {
+  /// Returns the name of the field the source is embedded as, or `None` if its fields are
+  /// flattened into the target.
+  ///
+  /// Unless given as `embed = name`, this is the source's snake case name.
+  pub fn embedded_name(&self) -> Option<syn::Ident> {
+    if !self.options.embed {
+      return None;
+    }
+
+    Some(
+      self
+        .options
+        .embed_name
+        .clone()
+        .unwrap_or_else(|| self.snake_case_name()),
+    )
+  }
+
+  /// Returns the source's name in snake case, so `Auditable` becomes `auditable` and `HttpHeaders`
+  /// becomes `http_headers`.
+  pub fn snake_case_name(&self) -> syn::Ident { snake_case(&self.path.path.segments.last().unwrap().ident) }
+}
+
+/// Collects the `#[cfg(...)]` attributes of a field, which the items generated for the field must
+/// carry as well.
+pub fn cfg_attrs(attrs:&[syn::Attribute]) -> Vec<syn::Attribute> {
+  attrs
+    .iter()
+    .filter(|attr| attr.path().is_ident("cfg"))
+    .cloned()
+    .collect()
+}
+
+/// Converts the name of a struct to snake case.
+pub fn snake_case(ident:&syn::Ident) -> syn::Ident {
+  let mut name = String::new();
+  for (i, c) in ident.unraw().to_string().chars().enumerate() {
+    if c.is_uppercase() {
+      if i > 0 {
+        name.push('_');
+      }
+      name.extend(c.to_lowercase());
+    } else {
+      name.push(c);
+    }
+  }
+  syn::Ident::new(&name, ident.span())
+}
+
+impl ToTokens for InjectSource {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    self.path.to_tokens(tokens);
+    if !self.options.is_empty() {
+      let options = &self.options;
+      tokens.extend(quote!((#options)));
+    }
+  }
+}
+
+/// Options controlling how the fields of one source are injected.
+///
+/// # Fields
+///
+/// * `prefix` - Prefix added to the name of every injected field, written `prefix = "billing_"`
+/// * `renames` - New names for individual fields, written `rename(id = base_id)`; a renamed
+///   field does not also get the prefix
+/// * `only` - The only fields to inject, written `only(created_at)`
+/// * `except` - Fields left out of the injection, written `except(created_by)`
+/// * `embed` - Whether the source is embedded as a single field instead of being flattened,
+///   written `embed` or `embed = field_name`
+/// * `embed_name` - Name of the embedded field, if not derived from the source's name
+/// * `vis` - Visibility of the injected fields, or of the embedded field, in place of their own,
+///   written `vis = pub(crate)`
+/// * `prepend` - Whether the positional fields of a tuple source go before the target's own fields
+///   rather than after them, written `prepend`
+#[derive(Default)]
+pub struct SourceOptions {
+  pub prefix:    Option<syn::LitStr>,
+  pub renames:   Vec<(syn::Ident, syn::Ident)>,
+  pub only:      Option<Vec<syn::Ident>>,
+  pub except:    Vec<syn::Ident>,
+  pub embed:     bool,
+  pub embed_name:Option<syn::Ident>,
+  pub vis:       Option<syn::Visibility>,
+  pub prepend:   bool,
+}
+
+impl SourceOptions {
+  /// Checks whether no option is set.
+  pub fn is_empty(&self) -> bool {
+    self.prefix.is_none()
+      && self.renames.is_empty()
+      && self.only.is_none()
+      && self.except.is_empty()
+      && !self.embed
+      && self.vis.is_none()
+      && !self.prepend
+  }
+
+  /// Returns the first option naming or embedding fields, which positional fields cannot take.
+  pub fn naming_option(&self) -> Option<&'static str> {
+    [
+      (self.embed, "embed"),
+      (self.prefix.is_some(), "prefix"),
+      (!self.renames.is_empty(), "rename"),
+      (self.only.is_some(), "only"),
+      (!self.except.is_empty(), "except"),
+    ]
+    .into_iter()
+    .find_map(|(set, option)| set.then_some(option))
+  }
+
+  /// Checks whether a field of the source is injected at all, given `only` and `except`.
+  pub fn includes(&self, name:&str) -> bool {
+    let listed = |fields:&Vec<syn::Ident>| fields.iter().any(|field| field == name);
+    self.only.as_ref().is_none_or(listed) && !listed(&self.except)
+  }
+
+  /// Returns the fields selected by `only` or `except`.
+  pub fn selected_fields(&self) -> impl Iterator<Item = &syn::Ident> { self.only.iter().flatten().chain(&self.except) }
+
+  /// Returns the name a field of the source is injected under.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// # use syn::parse_quote;
+  /// # use crate::types::SourceOptions;
+  /// let options:SourceOptions = syn::parse_quote!(prefix = "billing_", rename(id = address_id));
+  /// assert_eq!(options.field_name("street"), "billing_street");
+  /// assert_eq!(options.field_name("id"), "address_id");
+  /// ```
+  pub fn field_name(&self, name:&str) -> String {
+    if let Some((_, renamed)) = self.renames.iter().find(|(field, _)| field == name) {
+      return renamed.to_string();
+    }
+
+    match &self.prefix {
+      Some(prefix) => format!("{}{}", prefix.value(), name),
+      None => name.to_string(),
+    }
+  }
+
+  /// Returns the visibility a field of the source is injected with, given its visibility as
+  /// rebased onto the target.
+  pub fn field_vis(&self, vis:&VisibilityKind) -> VisibilityKind {
+    self.vis.as_ref().map_or_else(|| vis.clone(), visibility_to_kind)
+  }
+}
+
+impl Parse for SourceOptions {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let mut options = SourceOptions::default();
+    let mut embed_span = None;
+
+    while !input.is_empty() {
+      let option:syn::Ident = input.parse()?;
+      match option.to_string().as_str() {
+        "embed" => {
+          options.embed = true;
+          embed_span = Some(option.span());
+          if input.peek(syn::Token![=]) {
+            input.parse::<syn::Token![=]>()?;
+            options.embed_name = Some(input.call(syn::Ident::parse_any)?);
+          }
+        }
+        "prefix" => {
+          input.parse::<syn::Token![=]>()?;
+          options.prefix = Some(input.parse()?);
+        }
+        "vis" => {
+          input.parse::<syn::Token![=]>()?;
+          options.vis = Some(input.parse()?);
+        }
+        "prepend" => options.prepend = true,
+        "rename" => {
+          let content;
+          syn::parenthesized!(content in input);
+          while !content.is_empty() {
+            let field = content.call(syn::Ident::parse_any)?;
+            content.parse::<syn::Token![=]>()?;
+            options.renames.push((field, content.parse()?));
+            if !content.is_empty() {
+              content.parse::<syn::Token![,]>()?;
+            }
+          }
+        }
+        "only" | "except" => {
+          let content;
+          syn::parenthesized!(content in input);
+          let mut fields = Vec::new();
+          while !content.is_empty() {
+            fields.push(content.call(syn::Ident::parse_any)?);
+            if !content.is_empty() {
+              content.parse::<syn::Token![,]>()?;
+            }
+          }
+
+          if option == "only" {
+            options.only = Some(fields);
+          } else {
+            options.except = fields;
+          }
+          if options.only.is_some() && !options.except.is_empty() {
+            return Err(syn::Error::new(
+              option.span(),
+              "Cannot combine 'only' and 'except' for one source",
+            ));
+          }
+        }
+        _ => {
+          return Err(syn::Error::new(
+            option.span(),
+            format!(
+              "Unknown source option '{}'{}",
+              option,
+              suggestion(&option.to_string(), SOURCE_OPTIONS.iter().copied())
+            ),
+          ))
+        }
+      }
+
+      if !input.is_empty() {
+        input.parse::<syn::Token![,]>()?;
+      }
+    }
+
+    if let Some(span) = embed_span {
+      if options.prefix.is_some() || !options.renames.is_empty() || options.only.is_some() || !options.except.is_empty()
+      {
+        return Err(syn::Error::new(
+          span,
+          "An embedded source cannot be combined with options renaming or selecting its fields",
+        ));
+      }
+    }
+
+    Ok(options)
+  }
+}
+
+impl ToTokens for SourceOptions {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    let mut options = Vec::new();
+    if self.embed {
+      match &self.embed_name {
+        Some(name) => options.push(quote!(embed = #name)),
+        None => options.push(quote!(embed)),
+      }
+    }
+    if let Some(prefix) = &self.prefix {
+      options.push(quote!(prefix = #prefix));
+    }
+    if !self.renames.is_empty() {
+      let (fields, renamed):(Vec<_>, Vec<_>) = self.renames.iter().cloned().unzip();
+      options.push(quote!(rename(#(#fields = #renamed),*)));
+    }
+    if let Some(only) = &self.only {
+      options.push(quote!(only(#(#only),*)));
+    }
+    if !self.except.is_empty() {
+      let except = &self.except;
+      options.push(quote!(except(#(#except),*)));
+    }
+    if let Some(vis) = &self.vis {
+      options.push(quote!(vis = #vis));
+    }
+    if self.prepend {
+      options.push(quote!(prepend));
+    }
+    tokens.extend(quote!(#(#options),*));
+  }
+}
+
+/// Options of a field, given with `#[inject(...)]` on a field of a source or of a target.
+///
+/// Options on a source field travel with it into every target.
+///
+/// # Fields
+///
+/// * `default` - Value a builder falls back to when the field is not set, written `default` for
+///   `Default::default()` or `default = <expr>`
+/// * `overrides` - Whether a field of the target replaces the field of the same name injected by a
+///   source, written `override`
+/// * `vis` - Visibility the field is injected with, in place of its own, written
+///   `vis = pub(crate)`; it applies to the injected field only and does not travel further
+#[derive(Default)]
+pub struct FieldOptions {
+  pub default:  Option<syn::Expr>,
+  pub overrides:bool,
+  pub vis:      Option<syn::Visibility>,
+}
+
+impl FieldOptions {
+  /// Checks whether an attribute gives field options.
+  pub fn is_options_attr(attr:&syn::Attribute) -> bool { attr.path().is_ident("inject") }
+
+  /// Collects the options given by the `#[inject(...)]` attributes among a field's attributes.
+  pub fn from_attrs(attrs:&[syn::Attribute]) -> syn::Result<Self> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|attr| FieldOptions::is_options_attr(attr)) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("default") {
+          options.default = Some(if meta.input.peek(syn::Token![=]) {
+            meta.value()?.parse()?
+          } else {
+            syn::parse_quote!(::core::default::Default::default())
+          });
+          Ok(())
+        } else if meta.path.is_ident("override") {
+          options.overrides = true;
+          Ok(())
+        } else if meta.path.is_ident("vis") {
+          options.vis = Some(meta.value()?.parse()?);
+          Ok(())
+        } else {
+          let option = meta.path.to_token_stream().to_string();
+          Err(meta.error(format!(
+            "Unknown field option '{}'{}",
+            option,
+            suggestion(&option, FIELD_OPTIONS.iter().copied())
+          )))
+        }
+      })?;
+    }
+
+    Ok(options)
+  }
+
+  /// Writes the options as an `#[inject(...)]` attribute, `None` if no option is set.
+  pub fn to_attr(&self) -> Option<syn::Attribute> {
+    let mut options = Vec::new();
+    if let Some(default) = &self.default {
+      options.push(quote!(default = #default));
+    }
+    if self.overrides {
+      options.push(quote!(override));
+    }
+    if let Some(vis) = &self.vis {
+      options.push(quote!(vis = #vis));
+    }
+    (!options.is_empty()).then(|| syn::parse_quote!(#[inject(#(#options),*)]))
+  }
+}
+
+/// Options of a variant of an enum target, given with `#[inject(...)]` on the variant.
+///
+/// # Fields
+///
+/// * `skip` - Whether the variant is left without the injected fields, written `skip`
+#[derive(Default)]
+pub struct VariantOptions {
+  pub skip:bool,
+}
+
+impl VariantOptions {
+  /// Collects the options given by the `#[inject(...)]` attributes among a variant's attributes.
+  pub fn from_attrs(attrs:&[syn::Attribute]) -> syn::Result<Self> {
+    let mut options = VariantOptions::default();
+    for attr in attrs.iter().filter(|attr| FieldOptions::is_options_attr(attr)) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("skip") {
+          options.skip = true;
+          Ok(())
+        } else {
+          let option = meta.path.to_token_stream().to_string();
+          Err(meta.error(format!(
+            "Unknown variant option '{}'{}",
+            option,
+            suggestion(&option, VARIANT_OPTIONS.iter().copied())
+          )))
+        }
+      })?;
+    }
+
+    Ok(options)
+  }
+}
+
+/// Filter selecting which attributes of source fields are carried over to injected fields.
+///
+/// Attributes are matched by their path, so `serde` matches `#[serde(rename = "id")]` and `doc`
+/// matches `///` comments.
+///
+/// # Variants
+///
+/// * `KeepAll` - Carry over every attribute (the default)
+/// * `StripAll` - Carry over no attributes, written `strip_attrs`
+/// * `Strip` - Carry over all but the listed attributes, written `strip_attrs(serde, validate)`
+/// * `Keep` - Carry over only the listed attributes, written `keep_attrs(doc, cfg)`
+#[derive(Default)]
+pub enum AttrFilter {
+  #[default]
+  KeepAll,
+  StripAll,
+  Strip(Vec<syn::Path>),
+  Keep(Vec<syn::Path>),
+}
+
+impl AttrFilter {
+  /// Checks whether the input continues with an attribute filter rather than a source path.
+  fn peek(input:ParseStream) -> bool {
+    let fork = input.fork();
+    match fork.parse::<syn::Ident>() {
+      Ok(ident) if ident == "strip_attrs" || ident == "keep_attrs" => {
+        fork.is_empty() || fork.peek(syn::Token![,]) || fork.peek(syn::token::Paren)
+      }
+      _ => false,
+    }
+  }
+
+  /// Checks whether an attribute of a source field is carried over to the injected field.
+  pub fn retains(&self, attr:&syn::Attribute) -> bool {
+    match self {
+      AttrFilter::KeepAll => true,
+      AttrFilter::StripAll => false,
+      AttrFilter::Strip(paths) => !paths.iter().any(|path| attr.path() == path),
+      AttrFilter::Keep(paths) => paths.iter().any(|path| attr.path() == path),
+    }
+  }
+}
+
+impl Parse for AttrFilter {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let ident:syn::Ident = input.parse()?;
+    if !input.peek(syn::token::Paren) {
+      return match ident.to_string().as_str() {
+        "strip_attrs" => Ok(AttrFilter::StripAll),
+        _ => {
+          Err(syn::Error::new(
+            ident.span(),
+            "keep_attrs requires a list of attribute paths",
+          ))
+        }
+      };
+    }
+
+    let content;
+    syn::parenthesized!(content in input);
+    let paths = content
+      .parse_terminated(syn::Path::parse_mod_style, syn::Token![,])?
+      .into_iter()
+      .collect();
+
+    Ok(match ident.to_string().as_str() {
+      "strip_attrs" => AttrFilter::Strip(paths),
+      _ => AttrFilter::Keep(paths),
     })
   }
 }
 
+impl ToTokens for AttrFilter {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    tokens.extend(match self {
+      AttrFilter::KeepAll => quote!(),
+      AttrFilter::StripAll => quote!(strip_attrs),
+      AttrFilter::Strip(paths) => quote!(strip_attrs(#(#paths),*)),
+      AttrFilter::Keep(paths) => quote!(keep_attrs(#(#paths),*)),
+    });
+  }
+}
+
+/// The struct that declares a field, identifying the field wherever it is injected.
+///
+/// Written `Base, id, 10370938124658436203` on each field published by a callback macro: the
+/// declaring struct, the field's name in it and a hash of the struct's definition site. A field
+/// arriving through several sources is the same field if its origins are equal, which tells a
+/// shared ancestor apart from unrelated structs declaring fields of the same name.
+///
+/// # Fields
+///
+/// * `source` - Name of the declaring struct
+/// * `field` - Name of the field in the declaring struct
+/// * `site` - Hash of the declaring struct's definition site
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldOrigin {
+  pub source:syn::Ident,
+  pub field: syn::Ident,
+  pub site:  u64,
+}
+
+impl FieldOrigin {
+  /// Creates the origin of a field declared by a struct.
+  pub fn new(source:&syn::Ident, field:&syn::Ident, site:u64) -> Self {
+    FieldOrigin {
+      source:source.clone(),
+      field:field.clone(),
+      site,
+    }
+  }
+}
+
+impl Parse for FieldOrigin {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let source = input.parse()?;
+    input.parse::<syn::Token![,]>()?;
+    let field = input.call(syn::Ident::parse_any)?;
+    input.parse::<syn::Token![,]>()?;
+    let site = input.parse::<syn::LitInt>()?.base10_parse()?;
+
+    Ok(FieldOrigin { source, field, site })
+  }
+}
+
+impl ToTokens for FieldOrigin {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    let FieldOrigin { source, field, site } = self;
+    let site = proc_macro2::Literal::u64_unsuffixed(*site);
+    tokens.extend(quote!(#source, #field, #site));
+  }
+}
+
+/// What a [`FieldSet`] is generated for.
+///
+/// # Variants
+///
+/// * `Accessors` - An accessor trait, implemented by each struct carrying the fields
+/// * `View` - Borrowed and mutable views, returned by methods of each struct carrying the fields
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldSetKind {
+  Accessors,
+  View,
+}
+
+/// The fields of an injectable struct as found in a struct carrying them, as passed on by
+/// callback macros.
+///
+/// Written `accessors self::HasAuditable<T> { created_at: u64 = created_at, ... }` for an
+/// accessor trait, or `view self::Auditable<T> { ... }` for the views of the struct, listing each
+/// field with its type and its place within the carrying struct. A place has more than one
+/// segment when the field lives in an embedded source, as in `auditable.created_at`. A field
+/// declared under `#[cfg(...)]` is preceded by its cfg attributes.
+///
+/// # Fields
+///
+/// * `kind` - Whether the set describes an accessor trait or views
+/// * `path` - Path of the accessor trait or of the viewed struct, including generic arguments
+/// * `fields` - The fields in the set
+#[derive(Clone, Debug)]
+pub struct FieldSet {
+  pub kind:  FieldSetKind,
+  pub path:  syn::Path,
+  pub fields:Vec<FieldSetField>,
+}
+
+/// One field of a [`FieldSet`].
+///
+/// # Fields
+///
+/// * `cfgs` - The `#[cfg(...)]` attributes of the field
+/// * `name` - Name of the field in the struct declaring it
+/// * `ty` - Type of the field
+/// * `place` - Dotted path to the field within the carrying struct
+#[derive(Clone, Debug)]
+pub struct FieldSetField {
+  pub cfgs: Vec<syn::Attribute>,
+  pub name: syn::Ident,
+  pub ty:   syn::Type,
+  pub place:syn::punctuated::Punctuated<syn::Ident, syn::Token![.]>,
+}
+
+impl Parse for FieldSet {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let kind:syn::Ident = input.parse()?;
+    let kind = match kind.to_string().as_str() {
+      "accessors" => FieldSetKind::Accessors,
+      "view" => FieldSetKind::View,
+      _ => return Err(syn::Error::new(kind.span(), format!("Unknown field set '{}'", kind))),
+    };
+    let path = input.parse()?;
+    let content;
+    syn::braced!(content in input);
+    let fields = content.parse_terminated(FieldSetField::parse, syn::Token![,])?;
+
+    Ok(FieldSet {
+      kind,
+      path,
+      fields:fields.into_iter().collect(),
+    })
+  }
+}
+
+impl ToTokens for FieldSet {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    let kind = match self.kind {
+      FieldSetKind::Accessors => quote!(accessors),
+      FieldSetKind::View => quote!(view),
+    };
+    let path = &self.path;
+    let fields = &self.fields;
+    tokens.extend(quote!(#kind #path { #(#fields),* }));
+  }
+}
+
+impl Parse for FieldSetField {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let cfgs = input.call(syn::Attribute::parse_outer)?;
+    let name = input.call(syn::Ident::parse_any)?;
+    input.parse::<syn::Token![:]>()?;
+    let ty = input.parse()?;
+    input.parse::<syn::Token![=]>()?;
+    let place = syn::punctuated::Punctuated::parse_separated_nonempty_with(input, syn::Ident::parse_any)?;
+
+    Ok(FieldSetField { cfgs, name, ty, place })
+  }
+}
+
+impl ToTokens for FieldSetField {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    let FieldSetField { cfgs, name, ty, place } = self;
+    tokens.extend(quote!(#(#cfgs)* #name: #ty = #place));
+  }
+}
+
+/// The fields one source added to a target.
+///
+/// # Fields
+///
+/// * `embedded` - Name of the field the source is embedded as, if it is not flattened
+/// * `fields` - Names or indices of the flattened fields, in the source and in the target
+/// * `shared` - Names of the fields an earlier source already added, arriving from the same
+///   struct as in a diamond, in the source and in the target
+/// * `complete` - Whether every field of the source made it into the target, added by this source
+///   or shared with an earlier one
+pub struct InjectedFields {
+  pub embedded:Option<syn::Ident>,
+  pub fields:  Vec<(syn::Member, syn::Member)>,
+  pub shared:  Vec<(syn::Member, syn::Member)>,
+  pub complete:bool,
+}
+
+/// State threaded through the callback macros while resolving the sources of one target.
+///
+/// Written as `{ <config> } { <target> } ({ <source> } [<impl>]?)*`: `#[inject_fields]` emits the
+/// first two groups and the callback macro of each source appends that source's definition,
+/// followed by its `#[injectable]` impl block if it has one, before handing the state back to
+/// `__inject_fields_expand!`.
+///
+/// # Fields
+///
+/// * `config` - The injection configuration of the target
+/// * `target` - The target struct, without the `#[inject_fields]` attribute being expanded
+/// * `sources` - Definitions of the sources resolved so far, in configuration order
+/// * `mixins` - The `#[injectable]` impl block of each resolved source, if it has one
+pub struct InjectionState {
+  pub config: InjectConfig,
+  pub target: syn::DeriveInput,
+  pub sources:Vec<syn::DeriveInput>,
+  pub mixins: Vec<Option<syn::ItemImpl>>,
+}
+
+impl Parse for InjectionState {
+  fn parse(input:ParseStream) -> syn::Result<Self> {
+    let config;
+    syn::braced!(config in input);
+    let target;
+    syn::braced!(target in input);
+
+    let mut sources = Vec::new();
+    let mut mixins = Vec::new();
+    while !input.is_empty() {
+      let source;
+      syn::braced!(source in input);
+      sources.push(source.parse()?);
+      mixins.push(if input.peek(syn::token::Bracket) {
+        let mixin;
+        syn::bracketed!(mixin in input);
+        Some(mixin.parse()?)
+      } else {
+        None
+      });
+    }
+
+    Ok(InjectionState {
+      config:config.parse()?,
+      target:target.parse()?,
+      sources,
+      mixins,
+    })
+  }
+}
+
+impl ToTokens for InjectionState {
+  fn to_tokens(&self, tokens:&mut TokenStream) {
+    let config = &self.config;
+    let target = &self.target;
+    let sources = self.sources.iter().zip(&self.mixins).map(|(source, mixin)| {
+      match mixin {
+        Some(mixin) => quote!({ #source } [#mixin]),
+        None => quote!({ #source }),
+      }
+    });
+    tokens.extend(quote!({ #config } { #target } #(#sources)*));
+  }
+}
+
 /// Error type for injection-related failures.
 ///
 /// Wraps a string error message describing what went wrong during
@@ -0,0 +1,263 @@
+//! Injection into the variants of enums.
+//!
+//! `#[inject_fields]` on an enum injects the fields of its sources into every struct-like variant,
+//! turning unit variants into struct-like ones. A variant marked `#[inject(skip)]` is left as it
+//! is, and tuple variants must be. The enum gets a getter and a `_mut` accessor for each injected
+//! field, returning an `Option` if some variants do not carry the field.
+
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+
+use crate::{
+  error::Diagnostics,
+  registry::{duplicate_field_error, is_origin_attr, process_type_paths, unused_override_error},
+  types::{FieldOptions, InjectConfig, InjectedFields, ModuleInfo, VariantOptions},
+};
+
+/// A field injected into the variants of an enum.
+///
+/// # Fields
+///
+/// * `field` - The injected field
+/// * `variants` - The variants carrying the field, in declaration order
+pub struct SharedField {
+  pub field:   syn::Field,
+  pub variants:Vec<syn::Ident>,
+}
+
+/// Injects the fields of the sources into the variants of an enum.
+///
+/// The fields are resolved once, as if for a struct with no fields of its own, then added to each
+/// variant that is not skipped. A variant's own field of the same name as an injected one is an
+/// error, unless marked `#[inject(override)]`, in which case the variant keeps its own field.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source struct, in the same order as `config.structs`
+/// * `data` - The variants of the enum
+///
+/// # Returns
+///
+/// * `Ok((Vec<InjectedFields>, Vec<SharedField>))` with the fields each source added, in
+///   configuration order, and the variants carrying each injected field
+/// * `Err(syn::Error)` combining every validation error
+pub fn inject_variants(
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
+  data:&mut syn::DataEnum,
+) -> syn::Result<(Vec<InjectedFields>, Vec<SharedField>)> {
+  let mut fields = syn::FieldsNamed {
+    brace_token:Default::default(),
+    named:      Default::default(),
+  };
+  let injected = process_type_paths(config, sources, &mut fields, &HashSet::new())?;
+
+  // Fields of variants have the visibility of the enum, and no macro reads their options
+  let mut shared:Vec<_> = fields
+    .named
+    .into_iter()
+    .map(|mut field| {
+      field.vis = syn::Visibility::Inherited;
+      field
+        .attrs
+        .retain(|attr| !FieldOptions::is_options_attr(attr) && !is_origin_attr(attr));
+      SharedField {
+        field,
+        variants:Vec::new(),
+      }
+    })
+    .collect();
+
+  // The source each field is injected from, to name it in errors
+  let origins:HashMap<String, &syn::Ident> = config
+    .structs
+    .iter()
+    .zip(&injected)
+    .flat_map(|(injection, fields)| {
+      let source = &injection.path.path.segments.last().unwrap().ident;
+      fields
+        .fields
+        .iter()
+        .map(move |(_, name)| (quote!(#name).to_string(), source))
+    })
+    .collect();
+
+  let mut diagnostics = Diagnostics::default();
+  for variant in &mut data.variants {
+    let Some(options) = diagnostics.take(VariantOptions::from_attrs(&variant.attrs)) else {
+      continue;
+    };
+    variant.attrs.retain(|attr| !FieldOptions::is_options_attr(attr));
+    if options.skip {
+      continue;
+    }
+
+    let own = match &mut variant.fields {
+      syn::Fields::Named(own) => own,
+      syn::Fields::Unit => {
+        variant.fields = syn::Fields::Named(syn::FieldsNamed {
+          brace_token:Default::default(),
+          named:      Default::default(),
+        });
+        let syn::Fields::Named(own) = &mut variant.fields else {
+          unreachable!()
+        };
+        own
+      }
+      syn::Fields::Unnamed(own) => {
+        diagnostics.push(syn::Error::new(
+          own.span(),
+          format!(
+            "Cannot inject named fields into tuple variant {}; mark it #[inject(skip)] to leave it out",
+            variant.ident
+          ),
+        ));
+        continue;
+      }
+    };
+
+    // Own fields marked as overrides are kept in place of the injected fields of the same name
+    let mut overrides = HashMap::new();
+    for field in own.named.iter_mut() {
+      let Some(options) = diagnostics.take(FieldOptions::from_attrs(&field.attrs)) else {
+        continue;
+      };
+      field.attrs.retain(|attr| !FieldOptions::is_options_attr(attr));
+      let name = field.ident.as_ref().unwrap();
+      if options.overrides {
+        overrides.insert(name.to_string(), name.span());
+      }
+    }
+    let declared:HashMap<String, proc_macro2::Span> = own
+      .named
+      .iter()
+      .filter_map(|field| field.ident.as_ref())
+      .map(|ident| (ident.to_string(), ident.span()))
+      .collect();
+
+    for shared in &mut shared {
+      let name = shared.field.ident.as_ref().unwrap().to_string();
+      if let Some(span) = declared.get(&name) {
+        if overrides.remove(&name).is_none() {
+          diagnostics.push(duplicate_field_error(*span, &name, origins[&name]));
+        }
+        continue;
+      }
+      own.named.push(shared.field.clone());
+      shared.variants.push(variant.ident.clone());
+    }
+
+    let mut unused:Vec<_> = overrides.into_iter().collect();
+    unused.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, span) in unused {
+      diagnostics.push(unused_override_error(span, &name));
+    }
+  }
+
+  diagnostics.finish()?;
+  Ok((injected, shared))
+}
+
+/// Generates the accessors of an enum to the fields injected into its variants.
+///
+/// Each field gets a getter named after it and a `_mut` accessor, with the enum's visibility.
+/// They return references if every variant carries the field, and `Option`s otherwise. Fields no
+/// variant carries get no accessors.
+///
+/// # Arguments
+///
+/// * `target` - The enum, with its fields injected
+/// * `shared` - The injected fields and the variants carrying them
+///
+/// # Returns
+///
+/// An inherent impl with the accessors
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // For `#[inject_fields(Envelope)] enum Event { Created { .. }, Deleted { .. } }`, with
+/// // `Envelope { id: u64 }`:
+/// impl Event {
+///   pub fn id(&self) -> &u64 {
+///     match self {
+///       Self::Created { id, .. } => id,
+///       Self::Deleted { id, .. } => id,
+///     }
+///   }
+///   pub fn id_mut(&mut self) -> &mut u64 { ... }
+/// }
+/// ```
+pub fn variant_accessors(target:&syn::DeriveInput, shared:&[SharedField]) -> TokenStream {
+  let syn::Data::Enum(data) = &target.data else {
+    unreachable!("variant accessors are generated for enums");
+  };
+  let ident = &target.ident;
+  let vis = &target.vis;
+  let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+
+  let accessors = shared
+    .iter()
+    .filter(|shared| !shared.variants.is_empty())
+    .map(|shared| {
+      let name = shared.field.ident.as_ref().unwrap();
+      let name_mut = format_ident!("{}_mut", name);
+      let ty = &shared.field.ty;
+      let variants = &shared.variants;
+      let doc = format!("Returns `{}`, injected into the variants of [`{}`].", name, ident);
+      let doc_mut = format!(
+        "Returns `{}` mutably, injected into the variants of [`{}`].",
+        name, ident
+      );
+
+      if variants.len() == data.variants.len() {
+        quote! {
+          #[doc = #doc]
+          #vis fn #name(&self) -> &#ty {
+            match self {
+              #(Self::#variants { #name, .. } => #name,)*
+            }
+          }
+
+          #[doc = #doc_mut]
+          #vis fn #name_mut(&mut self) -> &mut #ty {
+            match self {
+              #(Self::#variants { #name, .. } => #name,)*
+            }
+          }
+        }
+      } else {
+        quote! {
+          #[doc = #doc]
+          #vis fn #name(&self) -> ::core::option::Option<&#ty> {
+            match self {
+              #(Self::#variants { #name, .. } => ::core::option::Option::Some(#name),)*
+              _ => ::core::option::Option::None,
+            }
+          }
+
+          #[doc = #doc_mut]
+          #vis fn #name_mut(&mut self) -> ::core::option::Option<&mut #ty> {
+            match self {
+              #(Self::#variants { #name, .. } => ::core::option::Option::Some(#name),)*
+              _ => ::core::option::Option::None,
+            }
+          }
+        }
+      }
+    })
+    .collect::<Vec<_>>();
+  if accessors.is_empty() {
+    return TokenStream::new();
+  }
+
+  quote! {
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #(#accessors)*
+    }
+  }
+}
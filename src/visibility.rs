@@ -2,11 +2,17 @@
 //!
 //! This module provides functionality for:
 //! - Converting between Rust's visibility syntax and internal visibility representation
-//! - Validating field visibility rules during injection
-//! - Enforcing module boundary access rules
+//! - Checking that the target's module may access the fields it injects
 
-use quote::ToTokens;
-use syn::Visibility;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{spanned::Spanned, Visibility};
+
+use crate::{
+  mixins,
+  registry::{describe, module_marker_ident},
+  types::{cfg_attrs, InjectConfig, InjectedFields, ModuleInfo},
+};
 
 /// Internal representation of Rust visibility rules.
 ///
@@ -93,56 +99,642 @@ pub fn kind_to_visibility(kind:&VisibilityKind) -> Visibility {
   }
 }
 
-/// Checks if a field with given visibility can be accessed from one module in another.
+/// Checks whether a visibility given to an injected field widens it beyond the crate.
 ///
-/// This function implements Rust's visibility rules to determine if field injection
-/// would violate any access restrictions.
+/// A field that is not `pub` in its source may be injected with any visibility up to
+/// `pub(crate)`, provided the target can access it at all; making it `pub` would expose it
+/// outside the crate its source keeps it in.
 ///
 /// # Arguments
 ///
-/// * `vis` - Visibility of the field
-/// * `target_module` - Module path where the field is defined
-/// * `source_module` - Module path trying to access the field
+/// * `vis` - Visibility of the field in its source
+/// * `adjusted` - Visibility given with `vis = ...`
 ///
 /// # Returns
 ///
-/// `true` if the field can be accessed, `false` otherwise
+/// `true` if the adjusted visibility must be rejected
+pub fn widens_beyond_crate(vis:&VisibilityKind, adjusted:&Visibility) -> bool {
+  *vis != VisibilityKind::Public && matches!(adjusted, Visibility::Public(_))
+}
+
+/// Checks whether an item is visible wherever another item of the same module is.
+///
+/// Restricted visibilities are compared by the module they restrict an item to: the item is
+/// visible wherever the other is if its module is the other's or one of its ancestors. Modules
+/// whose relation the paths do not tell, such as `super` and `crate::a`, are taken not to contain
+/// one another.
+///
+/// # Arguments
+///
+/// * `vis` - Visibility of the item
+/// * `other` - Visibility of the other item
+///
+/// # Returns
+///
+/// `true` if `vis` is at least as wide as `other`
 ///
 /// # Examples
 ///
 /// ```rust,ignore
-/// # use crate::visibility::{VisibilityKind, can_access_field};
-/// let can_access = can_access_field(
-///     &VisibilityKind::Public,
-///     "crate::models",
-///     "crate::controllers"
-/// );
-/// assert!(can_access);
+/// # use syn::parse_quote;
+/// # use crate::visibility::is_visible_as;
+/// assert!(is_visible_as(&parse_quote!(pub(crate)), &parse_quote!(pub(super))));
+/// assert!(!is_visible_as(&syn::Visibility::Inherited, &parse_quote!(pub)));
 /// ```
-pub fn can_access_field(vis:&VisibilityKind, target_module:&str, source_module:&str) -> bool {
+pub fn is_visible_as(vis:&Visibility, other:&Visibility) -> bool {
+  match (restriction(vis), restriction(other)) {
+    (None, _) => true,
+    (Some(_), None) => false,
+    (Some(module), Some(other)) => module.contains(&other),
+  }
+}
+
+/// Returns the module a visibility restricts an item to, from the item's own module, or `None`
+/// for `pub`.
+fn restriction(vis:&Visibility) -> Option<ModulePath> {
   match vis {
-    VisibilityKind::Public => true,
-    VisibilityKind::Private => {
-      // Private fields can only be injected within the same module
-      if target_module.is_empty() && source_module.is_empty() {
-        true
-      } else {
-        let same_module = source_module == target_module;
-        let is_child =
-          source_module.starts_with(target_module) && source_module[target_module.len()..].starts_with("::");
-        same_module || is_child
+    Visibility::Public(_) => None,
+    Visibility::Inherited => Some(ModulePath::target()),
+    Visibility::Restricted(restricted) => {
+      let segments = restricted.path.segments.iter().map(|segment| &segment.ident);
+      Some(
+        ModulePath::target()
+          .join_all(segments)
+          .unwrap_or_else(ModulePath::target),
+      )
+    }
+  }
+}
+
+/// Rebases the visibility of a source field onto the module of the target.
+///
+/// Relative visibilities (private, `pub(self)`, `pub(super)` and `pub(in path)` with a path not
+/// starting at `crate`) are relative to the source's module, located from the target's module by
+/// [`ModulePath::of_source`]. The module a field is visible in is resolved from there, then
+/// written again from the target's module: as `pub(in crate::...)` when the path is absolute, as a
+/// number of `super`s when the module is an ancestor of the target's. `pub`, `pub(crate)` and
+/// `pub(in crate::...)` mean the same everywhere and are kept, as are visibilities of sources named
+/// through an extern path.
+///
+/// A module that is neither absolute nor an ancestor of the target's module cannot contain the
+/// target, so the field is not accessible to it and is rejected by the access checks; it is made
+/// private in the meantime.
+///
+/// # Arguments
+///
+/// * `vis` - Visibility of the field in its source
+/// * `module` - Module defining the source, `None` for a source named through an extern path
+///
+/// # Returns
+///
+/// The visibility the field must have in the target to be visible in the same module
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// # use syn::parse_quote;
+/// # use crate::visibility::{rebase_visibility, ModulePath, VisibilityKind};
+/// // `pub(super)` in `super::Base` is visible two modules up from the target
+/// let module = ModulePath::of_source(&parse_quote!(super::Base));
+/// let rebased = rebase_visibility(&VisibilityKind::Restricted("super".to_string()), module.as_ref());
+/// assert_eq!(rebased, VisibilityKind::Restricted("super :: super".to_string()));
+/// ```
+pub fn rebase_visibility(vis:&VisibilityKind, module:Option<&ModulePath>) -> VisibilityKind {
+  match (relative_scope(vis), module) {
+    (Some(scope), Some(module)) => {
+      module
+        .clone()
+        .join_all(&scope)
+        .map_or_else(|| vis.clone(), |module| module.visibility())
+    }
+    _ => vis.clone(),
+  }
+}
+
+/// Checks whether rebasing a visibility depends on the module defining the source.
+///
+/// This is the case for a relative visibility resolving to more than the target's own module:
+/// were the source's module not the one the target names it through, the field would be visible
+/// in the wrong modules. A private result is the narrowest there is and needs no such check.
+///
+/// # Arguments
+///
+/// * `vis` - Visibility of the field in its source
+/// * `module` - Module defining the source, `None` for a source named through an extern path
+///
+/// # Returns
+///
+/// `true` if the target must name the source through the module defining it
+pub fn depends_on_module(vis:&VisibilityKind, module:Option<&ModulePath>) -> bool {
+  module.is_some() && relative_scope(vis).is_some() && rebase_visibility(vis, module) != VisibilityKind::Private
+}
+
+/// Returns the path a relative visibility restricts an item to, from the item's own module, or
+/// `None` for a visibility meaning the same everywhere.
+fn relative_scope(vis:&VisibilityKind) -> Option<Vec<syn::Ident>> {
+  match vis {
+    VisibilityKind::Public => None,
+    VisibilityKind::Private => Some(Vec::new()),
+    VisibilityKind::Restricted(path) => {
+      match syn::parse_str::<syn::Path>(path) {
+        Ok(path) if path.leading_colon.is_none() && path.segments[0].ident != "crate" => {
+          Some(path.segments.into_iter().map(|segment| segment.ident).collect())
+        }
+        _ => None,
       }
     }
-    VisibilityKind::Restricted(restriction) => {
-      match restriction.as_str() {
-        "crate" => true,
-        "super" => source_module.starts_with(target_module),
-        "self" => target_module == source_module,
-        path => {
-          let path = path.trim_start_matches("in ");
-          source_module.starts_with(path)
+  }
+}
+
+/// A module, located from the module of the target.
+///
+/// # Variants
+///
+/// * `Absolute` - The given modules down from the crate root
+/// * `Relative` - `supers` modules up from the target's module, then the given modules down
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModulePath {
+  Absolute(Vec<syn::Ident>),
+  Relative { supers:usize, modules:Vec<syn::Ident> },
+}
+
+impl ModulePath {
+  /// The target's own module.
+  fn target() -> Self {
+    Self::Relative {
+      supers: 0,
+      modules:Vec::new(),
+    }
+  }
+
+  /// The module a source is named through: the path written in `#[inject_fields(...)]` minus the
+  /// struct name, or `None` for an extern path or one reaching past the crate root.
+  pub fn of_source(type_path:&syn::TypePath) -> Option<Self> {
+    let path = &type_path.path;
+    if path.leading_colon.is_some() {
+      return None;
+    }
+    let segments = path.segments.iter().take(path.segments.len() - 1);
+    Self::target().join_all(segments.map(|segment| &segment.ident))
+  }
+
+  /// Steps along one segment of a path, `None` past the crate root.
+  fn join(mut self, segment:&syn::Ident) -> Option<Self> {
+    if segment == "crate" {
+      return Some(Self::Absolute(Vec::new()));
+    }
+    if segment == "self" {
+      return Some(self);
+    }
+
+    match &mut self {
+      Self::Absolute(modules) if segment == "super" => {
+        modules.pop()?;
+      }
+      Self::Relative { supers, modules } if segment == "super" => {
+        if modules.pop().is_none() {
+          *supers += 1;
         }
       }
+      Self::Absolute(modules) | Self::Relative { modules, .. } => modules.push(segment.clone()),
     }
+    Some(self)
+  }
+
+  /// Steps along every segment of a path in turn.
+  fn join_all<'a>(self, segments:impl IntoIterator<Item = &'a syn::Ident>) -> Option<Self> {
+    segments.into_iter().try_fold(self, Self::join)
+  }
+
+  /// Checks whether this module is `other` or one of its ancestors, as far as the paths tell.
+  fn contains(&self, other:&Self) -> bool {
+    match (self, other) {
+      (Self::Absolute(modules), Self::Absolute(others)) => others.starts_with(modules),
+      (Self::Absolute(modules), Self::Relative { .. }) => modules.is_empty(),
+      (Self::Relative { .. }, Self::Absolute(_)) => false,
+      (
+        Self::Relative { supers, modules },
+        Self::Relative {
+          supers: other_supers,
+          modules: others,
+        },
+      ) => (supers == other_supers && others.starts_with(modules)) || (supers > other_supers && modules.is_empty()),
+    }
+  }
+
+  /// The visibility of an item of the target's module restricted to this module.
+  fn visibility(&self) -> VisibilityKind {
+    match self {
+      Self::Absolute(modules) if modules.is_empty() => VisibilityKind::Restricted("crate".to_string()),
+      Self::Absolute(modules) => {
+        let modules:Vec<_> = modules.iter().map(ToString::to_string).collect();
+        VisibilityKind::Restricted(format!("crate :: {}", modules.join(" :: ")))
+      }
+      Self::Relative { supers: 0, .. } => VisibilityKind::Private,
+      Self::Relative { supers, modules } if modules.is_empty() => {
+        VisibilityKind::Restricted(vec!["super"; *supers].join(" :: "))
+      }
+      Self::Relative { .. } => VisibilityKind::Private,
+    }
+  }
+}
+
+/// Generates the checks that the target's module may access the non-public fields it injects.
+///
+/// A proc macro cannot tell which module it expands in, so access is left to rustc: for each
+/// source, a hidden function in the target's module reads every injected field that is not `pub`
+/// from a reference to the source. Private, `pub(super)` and `pub(in path)` fields out of reach
+/// of the target then fail to compile, with the error pointing at the source in
+/// `#[inject_fields(...)]`.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source, in configuration order
+/// * `injected` - The fields each source added to the target, in configuration order
+/// * `target` - The target struct, whose generics the source paths may use
+///
+/// # Returns
+///
+/// The access checks, empty if every injected field is public
+pub fn access_checks(
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
+  injected:&[InjectedFields],
+  target:&syn::DeriveInput,
+) -> TokenStream {
+  let (impl_generics, _, where_clause) = target.generics.split_for_impl();
+
+  let checks = config
+    .structs
+    .iter()
+    .zip(sources)
+    .zip(injected)
+    .enumerate()
+    .filter(|(_, (_, fields))| fields.embedded.is_none())
+    .filter_map(|(index, ((injection, source), fields))| {
+      let path = &injection.path;
+      let span = path.span();
+      let reads:Vec<_> = fields
+        .fields
+        .iter()
+        .filter_map(|(name, _)| {
+          let field = source
+            .fields
+            .iter()
+            .find(|field| name.to_token_stream().to_string() == field.name)?;
+          if field.vis == VisibilityKind::Public {
+            return None;
+          }
+          let cfgs = cfg_attrs(&field.attrs);
+          let name = match name {
+            syn::Member::Named(name) => syn::Member::Named(syn::Ident::new(&name.to_string(), span)),
+            syn::Member::Unnamed(index) => {
+              syn::Member::Unnamed(syn::Index {
+                index:index.index,
+                span,
+              })
+            }
+          };
+          Some(quote_spanned!(span=> #(#cfgs)* let _ = &source.#name;))
+        })
+        .collect();
+      if reads.is_empty() {
+        return None;
+      }
+
+      let check = format_ident!("__injectables_access_{}", index);
+      Some(quote_spanned! {span=>
+        #[allow(dead_code, non_snake_case)]
+        fn #check #impl_generics (source: &#path) #where_clause {
+          #(#reads)*
+        }
+      })
+    })
+    .collect::<Vec<_>>();
+  if checks.is_empty() {
+    return TokenStream::new();
+  }
+
+  quote! {
+    const _: () = {
+      #(#checks)*
+    };
+  }
+}
+
+/// Generates the checks that sources are named through the modules defining them.
+///
+/// Relative visibilities of fields, of embedded sources and of mixed-in methods are rebased onto
+/// the module a source is named through. Naming it through a `use` import would make them relative
+/// to the importing module instead, so wherever rebasing depends on the module (see
+/// [`depends_on_module`]), the module path the source publishes next to its callback macro is
+/// compared with the one the target names it through, located from the target's own
+/// `module_path!()`. A source named through an import then fails to compile, with the error pointing
+/// at the source in `#[inject_fields(...)]`; one imported explicitly does not have the published
+/// constant in scope, one imported through a glob publishes another module path.
+///
+/// # Arguments
+///
+/// * `config` - The injection configuration, with the source paths as written in `#[inject_fields(...)]`
+/// * `sources` - Module information of each source, in configuration order
+/// * `injected` - The fields each source added to the target, in configuration order
+/// * `mixins` - The `#[injectable]` impl block of each source, if it has one
+///
+/// # Returns
+///
+/// The module checks, empty if no visibility depends on the module of its source
+pub fn module_checks(
+  config:&InjectConfig,
+  sources:&[ModuleInfo],
+  injected:&[InjectedFields],
+  mixins:&[Option<syn::ItemImpl>],
+) -> TokenStream {
+  let checks = config
+    .structs
+    .iter()
+    .zip(sources)
+    .zip(injected)
+    .zip(mixins)
+    .filter_map(|(((injection, source), fields), mixin)| {
+      let module = source.module_path.as_ref()?;
+      let own_vis = injection.options.vis.is_none();
+      let relative = match &fields.embedded {
+        Some(_) => own_vis && source.relative,
+        None => {
+          own_vis
+            && fields.fields.iter().any(|(name, _)| {
+              let name = name.to_token_stream().to_string();
+              source.fields.iter().any(|field| field.name == name && field.relative)
+            })
+        }
+      };
+      let mixed = mixin.iter().flat_map(|mixin| &mixin.items).any(|item| {
+        let vis = match item {
+          syn::ImplItem::Fn(item) => &item.vis,
+          syn::ImplItem::Const(item) => &item.vis,
+          _ => return false,
+        };
+        mixins::item_ident(item).is_some_and(|ident| injection.options.includes(&ident.to_string()))
+          && depends_on_module(&visibility_to_kind(vis), Some(module))
+      });
+      if !relative && !mixed {
+        return None;
+      }
+
+      let path = &injection.path.path;
+      let span = path.span();
+      let name = &path.segments.last().unwrap().ident;
+      let mut marker = path.clone();
+      *marker.segments.last_mut().unwrap() = syn::PathSegment::from(module_marker_ident(name));
+      let (supers, absolute, modules) = match module {
+        ModulePath::Absolute(modules) => (0, true, modules),
+        ModulePath::Relative { supers, modules } => (*supers, false, modules),
+      };
+      let modules = modules.iter().map(ToString::to_string).collect::<Vec<_>>().join("::");
+      let message = format!(
+        "Cannot rebase the visibilities of {} onto the module it is named through, which does not define it; \
+         name it through the module that defines it rather than an import",
+        describe(&injection.path)
+      )
+      .replace('{', "{{")
+      .replace('}', "}}");
+      Some(quote_spanned! {span=>
+        ::core::assert!(
+          __injectables_defines(#marker, ::core::module_path!(), #supers, #absolute, #modules),
+          #message
+        );
+      })
+    })
+    .collect::<Vec<_>>();
+  if checks.is_empty() {
+    return TokenStream::new();
+  }
+
+  quote! {
+    const _: () = {
+      // Whether `module` is `path`, located from `target` the given number of modules up, or from
+      // the crate root when `absolute`; `str` equality is not available in constants
+      const fn __injectables_defines(module: &str, target: &str, supers: usize, absolute: bool, path: &str) -> bool {
+        let (module, target, path) = (module.as_bytes(), target.as_bytes(), path.as_bytes());
+        let mut start = if absolute { 0 } else { target.len() };
+        if absolute {
+          while start < target.len() && target[start] != b':' {
+            start += 1;
+          }
+        } else {
+          let mut supers = supers;
+          while supers > 0 {
+            while start > 0 && target[start - 1] != b':' {
+              start -= 1;
+            }
+            if start == 0 {
+              return false;
+            }
+            start -= 2;
+            supers -= 1;
+          }
+        }
+
+        let separator = if path.is_empty() { 0 } else { 2 };
+        if module.len() != start + separator + path.len() {
+          return false;
+        }
+        let mut index = 0;
+        while index < module.len() {
+          let expected = if index < start {
+            target[index]
+          } else if index < start + separator {
+            b':'
+          } else {
+            path[index - start - separator]
+          };
+          if module[index] != expected {
+            return false;
+          }
+          index += 1;
+        }
+        true
+      }
+
+      #(#checks)*
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use syn::parse_quote;
+
+  use super::*;
+
+  fn restricted(path:&str) -> VisibilityKind { VisibilityKind::Restricted(path.to_string()) }
+
+  fn idents(path:&str) -> Vec<syn::Ident> {
+    path
+      .split("::")
+      .filter(|segment| !segment.is_empty())
+      .map(|segment| format_ident!("{}", segment))
+      .collect()
+  }
+
+  #[test]
+  fn join() {
+    let relative = |supers, modules| {
+      ModulePath::Relative {
+        supers,
+        modules:idents(modules),
+      }
+    };
+    let cases = [
+      (ModulePath::target(), "crate", Some(ModulePath::Absolute(Vec::new()))),
+      (ModulePath::target(), "self", Some(ModulePath::target())),
+      (ModulePath::target(), "super", Some(relative(1, ""))),
+      (relative(1, ""), "super", Some(relative(2, ""))),
+      (relative(0, "a::b"), "super", Some(relative(0, "a"))),
+      (relative(1, "a"), "super", Some(relative(1, ""))),
+      (relative(1, ""), "a", Some(relative(1, "a"))),
+      (
+        ModulePath::Absolute(idents("a")),
+        "super",
+        Some(ModulePath::Absolute(Vec::new())),
+      ),
+      (ModulePath::Absolute(Vec::new()), "super", None),
+      (relative(2, "a"), "crate", Some(ModulePath::Absolute(Vec::new()))),
+      (
+        ModulePath::Absolute(idents("a")),
+        "self",
+        Some(ModulePath::Absolute(idents("a"))),
+      ),
+    ];
+    for (module, segment, expected) in cases {
+      assert_eq!(
+        module.clone().join(&format_ident!("{}", segment)),
+        expected,
+        "{:?} joined with {}",
+        module,
+        segment
+      );
+    }
+  }
+
+  #[test]
+  fn rebase() {
+    // Visibility in the source, the source as named from the target, visibility in the target
+    let cases = [
+      (
+        VisibilityKind::Public,
+        parse_quote!(super::Base),
+        VisibilityKind::Public,
+      ),
+      (restricted("crate"), parse_quote!(super::a::Base), restricted("crate")),
+      (
+        restricted("crate :: a"),
+        parse_quote!(a::b::Base),
+        restricted("crate :: a"),
+      ),
+      // Private and `self` are the source's module: the target's, a child of it or an ancestor
+      (VisibilityKind::Private, parse_quote!(Base), VisibilityKind::Private),
+      (
+        VisibilityKind::Private,
+        parse_quote!(self::Base),
+        VisibilityKind::Private,
+      ),
+      (restricted("self"), parse_quote!(b::Base), VisibilityKind::Private),
+      (VisibilityKind::Private, parse_quote!(super::Base), restricted("super")),
+      (
+        restricted("self"),
+        parse_quote!(super::super::Base),
+        restricted("super :: super"),
+      ),
+      // `super` steps up from the source's module
+      (restricted("super"), parse_quote!(Base), restricted("super")),
+      (restricted("super"), parse_quote!(b::Base), VisibilityKind::Private),
+      (
+        restricted("super"),
+        parse_quote!(super::Base),
+        restricted("super :: super"),
+      ),
+      (
+        restricted("super :: super"),
+        parse_quote!(b::c::Base),
+        VisibilityKind::Private,
+      ),
+      (
+        restricted("super :: super"),
+        parse_quote!(super::Base),
+        restricted("super :: super :: super"),
+      ),
+      // `in` paths are resolved like any other relative path
+      (restricted("super :: super"), parse_quote!(b::Base), restricted("super")),
+      (
+        restricted("self :: super"),
+        parse_quote!(super::Base),
+        restricted("super :: super"),
+      ),
+      // Siblings of the target cannot contain it, unlike their common ancestor
+      (
+        VisibilityKind::Private,
+        parse_quote!(super::sibling::Base),
+        VisibilityKind::Private,
+      ),
+      (
+        restricted("super"),
+        parse_quote!(super::sibling::Base),
+        restricted("super"),
+      ),
+      (
+        restricted("super"),
+        parse_quote!(super::sibling::inner::Base),
+        VisibilityKind::Private,
+      ),
+      // Absolute paths to the source give absolute visibilities
+      (
+        restricted("super"),
+        parse_quote!(crate::a::b::Base),
+        restricted("crate :: a"),
+      ),
+      (VisibilityKind::Private, parse_quote!(crate::Base), restricted("crate")),
+      (restricted("super"), parse_quote!(crate::a::Base), restricted("crate")),
+      // Extern paths are out of reach of relative visibilities
+      (restricted("super"), parse_quote!(::dep::Base), restricted("super")),
+    ];
+    for (vis, path, expected) in cases {
+      let path:syn::TypePath = path;
+      let module = ModulePath::of_source(&path);
+      assert_eq!(
+        rebase_visibility(&vis, module.as_ref()),
+        expected,
+        "{:?} in {}",
+        vis,
+        path.to_token_stream()
+      );
+    }
+  }
+
+  #[test]
+  fn imported_sources() {
+    // A `pub(super)` field of `crate::a::b::Base`, injected in `crate::a`
+    let named = ModulePath::of_source(&parse_quote!(b::Base));
+    assert_eq!(
+      rebase_visibility(&restricted("super"), named.as_ref()),
+      VisibilityKind::Private
+    );
+    assert!(!depends_on_module(&restricted("super"), named.as_ref()));
+
+    // Named through `use b::Base`, the source's module cannot be told from the path, so the
+    // target must check that it is named through the module defining it
+    let imported = ModulePath::of_source(&parse_quote!(Base));
+    assert!(depends_on_module(&restricted("super"), imported.as_ref()));
+    assert!(depends_on_module(
+      &VisibilityKind::Private,
+      ModulePath::of_source(&parse_quote!(super::Base)).as_ref()
+    ));
+
+    // Private results and visibilities meaning the same everywhere need no check
+    assert!(!depends_on_module(&VisibilityKind::Private, imported.as_ref()));
+    assert!(!depends_on_module(&restricted("crate"), imported.as_ref()));
+    assert!(!depends_on_module(&VisibilityKind::Public, imported.as_ref()));
+    assert!(!depends_on_module(&restricted("super"), None));
   }
 }
@@ -0,0 +1,60 @@
+//! Injectable structs exported for the cross-crate compilation tests.
+
+pub mod ids {
+  #[derive(Debug, Clone, Copy, PartialEq)]
+  pub struct UserId(pub u64);
+}
+
+pub mod audit {
+  use injectables::injectable;
+
+  use crate::ids::UserId;
+
+  #[injectable(export, accessors)]
+  pub struct Auditable {
+    pub created_by: UserId,
+    pub created_at: u64,
+  }
+
+  #[injectable(export, methods)]
+  pub struct Tagged<T> {
+    pub tags: Vec<T>,
+    pub owner: Option<crate::ids::UserId>,
+  }
+
+  #[injectable]
+  impl<T> Tagged<T> {
+    pub fn is_owned_by(&self, user: crate::ids::UserId) -> bool {
+      self.owner == Some(user)
+    }
+
+    pub fn tag_count(&self) -> usize {
+      self.tags.len()
+    }
+  }
+
+  #[injectable(export)]
+  pub struct Signature(pub UserId, pub u64);
+
+  #[injectable(export)]
+  pub struct Window<'a, const N: usize> {
+    pub viewer: &'a UserId,
+    pub recent: [UserId; N],
+  }
+}
+
+pub mod records {
+  use injectables::{inject_fields, injectable};
+
+  #[injectable(export)]
+  #[inject_fields(crate::audit::Auditable)]
+  pub struct Record {
+    pub revision: u32,
+  }
+
+  // Methods of an exported source are mixed in within its own crate as well
+  #[inject_fields(crate::audit::Tagged<u8>)]
+  pub struct Labels {
+    pub name: String,
+  }
+}
@@ -0,0 +1,25 @@
+use injectables::{injectable, inject_fields};
+
+pub trait Codec {
+  type Output;
+}
+
+pub trait Tagged {}
+
+impl Codec for u8 {
+  type Output = String;
+}
+
+impl Tagged for u8 {}
+
+#[injectable]
+pub struct Decoded<T: Codec + Tagged> {
+  pub value: T::Output,  // should fail - either bound of T may declare Output
+}
+
+#[inject_fields(Decoded<u8>)]
+pub struct Message {
+  pub id: u64,
+}
+
+fn main() {}
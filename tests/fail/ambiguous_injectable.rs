@@ -0,0 +1,16 @@
+use injectables::inject_fields;
+
+#[path = "../support/audit.rs"]
+mod audit;
+#[path = "../support/paging.rs"]
+mod paging;
+
+use audit::*;
+use paging::*;
+
+#[inject_fields(Meta)]  // should fail - both audit::Meta and paging::Meta are glob-imported
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
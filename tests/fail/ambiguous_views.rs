@@ -0,0 +1,23 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(views)]
+pub struct Pair<T> {
+  pub left: T,
+  pub right: T,
+}
+
+#[inject_fields(Pair<i32>(prefix = "num_"), Pair<String>(prefix = "text_"))]
+pub struct Mixed {
+  pub label: String,
+}
+
+fn main() {
+  let mixed = Mixed {
+    label: "m".to_string(),
+    num_left: 1,
+    num_right: 2,
+    text_left: "a".to_string(),
+    text_right: "b".to_string(),
+  };
+  mixed.as_pair_ref();  // should fail - the views of Pair<i32> and Pair<String> would clash
+}
@@ -0,0 +1,22 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Tracked {
+  pub id: u64,
+  #[cfg(any())]
+  pub trace: Vec<String>,
+}
+
+#[inject_fields(Tracked, conversions)]  // should fail - into_parts cannot leave out trace
+pub struct Order {
+  pub total: u32,
+}
+
+#[inject_fields(Tracked(embed), conversions)]
+pub struct Invoice {
+  pub number: u32,
+  #[cfg(any())]  // should fail - into_parts cannot leave out draft
+  pub draft: bool,
+}
+
+fn main() {}
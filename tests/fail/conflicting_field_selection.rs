@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Auditable {
+  pub created_at: u64,
+  pub created_by: String,
+}
+
+#[inject_fields(Auditable(only(created_at), except(created_by)))]  // should fail - only and except cannot be combined
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
@@ -5,7 +5,7 @@ pub struct GenericBase<T> {
   pub data: T,
 }
 
-#[inject_fields(GenericBase<i32>, GenericBase<String>)]  // should fail - conflicting types for 'data'
+#[inject_fields(GenericBase<i32>, GenericBase<String>)]  // should fail - conflicting instantiations of GenericBase
 pub struct ConflictingGenerics {
   pub name: String,
 }
@@ -0,0 +1,25 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Tagged<T> {
+  pub tag: T,
+}
+
+#[injectable]
+#[inject_fields(Tagged<u32>)]
+pub struct Named {
+  pub name: String,
+}
+
+#[injectable]
+#[inject_fields(Tagged<String>)]
+pub struct Versioned {
+  pub version: u32,
+}
+
+#[inject_fields(Named, Versioned)]  // should fail - Tagged arrives as both Tagged<u32> and Tagged<String>
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
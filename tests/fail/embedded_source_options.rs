@@ -0,0 +1,13 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[inject_fields(Base(embed, prefix = "base_"))]  // should fail - embedded fields cannot be prefixed
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
@@ -0,0 +1,41 @@
+mod a {
+  pub mod b {
+    use injectables::injectable;
+
+    #[injectable]
+    pub struct Base {
+      pub(super) id: u64, // visible in `a` only
+    }
+
+    #[injectable]
+    pub struct Tagged {
+      pub(super) tag: String,
+    }
+  }
+
+  mod imported {
+    use injectables::inject_fields;
+
+    use super::b::Base;
+
+    // should fail - `id` would be rebased onto `imported` rather than `b`
+    #[inject_fields(Base)]
+    pub struct Explicit {
+      pub name: String,
+    }
+  }
+
+  mod glob {
+    use injectables::inject_fields;
+
+    use super::b::*;
+
+    // should fail - a glob import does not move `Tagged` into `glob` either
+    #[inject_fields(Tagged)]
+    pub struct Glob {
+      pub name: String,
+    }
+  }
+}
+
+fn main() {}
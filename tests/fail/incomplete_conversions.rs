@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Auditable {
+  pub created_at: u64,
+  pub created_by: String,
+}
+
+#[inject_fields(Auditable(only(created_at)), conversions)]  // should fail - Auditable cannot be rebuilt without created_by
+pub struct Event {
+  pub name: String,
+}
+
+fn main() {}
@@ -1,14 +1,32 @@
-use injectables::{injectable, inject_fields};
+use injectables::{inject_fields, injectable};
 
 #[injectable]
 pub struct Base {
-    pub id: u64,
+  pub id: u64,
 }
 
 #[inject_fields(Base)]
-pub enum InvalidTarget {  // Should fail because enums can't receive injected fields
-    Variant1,
-    Variant2,
+pub enum Event {
+  Created { name: String },
+  Moved(u32, u32),  // should fail - tuple variants must be skipped
+  #[inject(skip, always)]  // should fail - unknown variant option
+  Deleted,
 }
 
-fn main() {}
\ No newline at end of file
+#[inject_fields(Base(embed), Base(vis = pub(crate)), builder)]  // should fail - enums are neither embedded into nor built
+pub enum Command {
+  Run,
+}
+
+#[inject_fields(Base)]
+pub enum Status {
+  Active {
+    id: u64,  // should fail - conflicts with the injected field
+  },
+  Inactive {
+    #[inject(override)]
+    reason: String,  // should fail - no source injects a field named reason
+  },
+}
+
+fn main() {}
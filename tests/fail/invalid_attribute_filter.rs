@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  /// Unique identifier
+  pub id: u64,
+}
+
+#[inject_fields(Base, keep_attrs)]  // should fail - keep_attrs needs the attributes to keep
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
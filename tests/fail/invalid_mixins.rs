@@ -0,0 +1,65 @@
+use injectables::injectable;
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+// should fail - Base does not take methods
+#[injectable]
+impl Base {
+  pub fn id(&self) -> u64 {
+    self.id
+  }
+}
+
+macro_rules! getter {
+  () => {
+    pub fn left(&self) -> &T {
+      &self.left
+    }
+  };
+}
+
+#[injectable(methods)]
+pub struct Pair<T> {
+  pub left: T,
+  pub right: T,
+}
+
+#[injectable(export)]  // should fail - options go on the struct
+impl<T: Clone> Pair<T> {
+  pub fn swapped(&self) -> Self {
+    Self {  // should fail - targets have fields of their own
+      left: self.right.clone(),
+      right: self.left.clone(),
+    }
+  }
+
+  pub fn into_left(self) -> T {
+    let Self { left, .. } = self;  // should fail - targets have fields of their own
+    left
+  }
+
+  getter!();  // should fail - only methods and constants are mixed in
+}
+
+#[injectable(methods)]
+pub struct Counter<T> {
+  pub count: T,
+}
+
+impl Clone for Counter<u8> {
+  fn clone(&self) -> Self {
+    Counter { count: self.count }
+  }
+}
+
+#[injectable]
+impl Counter<u8> {  // should fail - the impl block must be generic over T
+  pub fn increment(&mut self) {
+    self.count += 1;
+  }
+}
+
+fn main() {}
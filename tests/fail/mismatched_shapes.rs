@@ -0,0 +1,25 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+pub struct Point(pub i32, pub i32);
+
+// should fail - the fields of a tuple struct have no names to inject them under
+#[inject_fields(Point)]
+pub struct Named {
+  pub name: String,
+}
+
+// should fail - named fields cannot be injected positionally
+#[inject_fields(Base)]
+pub struct Tuple(pub u8);
+
+// should fail - accessor traits need named fields
+#[injectable(accessors)]
+pub struct Pair(pub u8, pub u8);
+
+fn main() {}
@@ -0,0 +1,19 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[inject_fields(Base, conversion)]  // should fail - conversions is misspelled
+pub struct Document {
+  #[inject(defualt)]  // should fail - default is misspelled
+  pub title: String,
+}
+
+#[inject_fields(Bsae)]  // should fail - Base is misspelled
+pub struct Comment {
+  pub body: String,
+}
+
+fn main() {}
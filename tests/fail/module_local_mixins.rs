@@ -0,0 +1,36 @@
+mod clock {
+  use std::collections::HashMap;
+
+  use injectables::injectable;
+
+  fn now() -> u64 {
+    42
+  }
+
+  const STEP: u64 = 1;
+
+  #[injectable(methods)]
+  pub struct Clock {
+    pub ticks: u64,
+  }
+
+  #[injectable]
+  impl Clock {
+    pub fn stamp(&mut self) -> u64 {
+      self.ticks = now();  // should fail - `now` is private to `clock`
+      self.ticks
+    }
+
+    pub fn tick(&mut self) {
+      self.ticks += STEP;  // should fail - so is `STEP`
+    }
+
+    pub fn counts(&self) -> HashMap<u64, u64> {  // should fail - `HashMap` is imported in `clock` only
+      let mut counts = HashMap::new();  // should fail
+      counts.insert(self.ticks, 1);
+      counts
+    }
+  }
+}
+
+fn main() {}
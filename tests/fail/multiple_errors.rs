@@ -0,0 +1,22 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[injectable]
+pub struct Auditable {
+  pub created_by: String,
+  pub created_at: u64,
+}
+
+// should fail - every problem is reported at once, at the offending name
+#[inject_fields(Base(rename(identifer = base_id)), Auditable(only(created_by, updated_at)))]
+pub struct Document {
+  #[inject(override)]
+  pub title: String,
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+mod audit {
+  use injectables::injectable;
+
+  #[injectable]
+  pub struct Auditable {
+    pub created_by: String,
+  }
+}
+
+mod api {
+  use injectables::inject_fields;
+
+  #[inject_fields(crate::audit::Auditable(vis = pub(self)))]
+  #[derive(Default)]
+  pub struct Document {
+    pub title: String,
+  }
+}
+
+fn main() {
+  let document = api::Document::default();
+  // should fail - `created_by` is private to `api` in Document
+  println!("{}", document.created_by);
+}
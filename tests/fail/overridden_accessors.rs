@@ -0,0 +1,21 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(accessors)]
+pub struct Base {
+  pub id: u64,
+}
+
+#[inject_fields(Base)]
+pub struct Document {
+  #[inject(override)]
+  pub id: String,
+}
+
+fn id_of(value: &impl HasBase) -> u64 {
+  *value.id()
+}
+
+fn main() {
+  let document = Document { id: "doc-1".to_string() };
+  id_of(&document);  // should fail - the overridden id is not the id of Base
+}
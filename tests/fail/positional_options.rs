@@ -0,0 +1,21 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+pub struct Point(pub i32, pub i32);
+
+// should fail - positional fields cannot be prefixed, and tuple targets have no builder
+#[inject_fields(Point(prefix = "p_"), builder)]
+pub struct Labeled(pub u8);
+
+// should fail - only positional fields can be put before the target's own fields
+#[inject_fields(Base(prepend))]
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
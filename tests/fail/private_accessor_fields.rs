@@ -0,0 +1,18 @@
+mod vault {
+  use injectables::injectable;
+
+  #[injectable(accessors)]
+  pub struct Vault {
+    pub label: String,
+    pub(super) owner: String,
+    secret: u32,
+  }
+}
+
+fn reset(vault: &mut impl vault::HasVault) -> &String {
+  *vault.secret_mut() = 7;  // should fail - secret is private to vault
+  let _ = vault.owner();  // should fail - owner is not visible wherever HasVault is
+  vault.label()
+}
+
+fn main() {}
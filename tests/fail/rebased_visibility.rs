@@ -0,0 +1,25 @@
+mod a {
+  use injectables::injectable;
+
+  #[injectable]
+  pub struct Parent {
+    id: u64,  // visible in `a` only
+  }
+
+  pub mod b {
+    use injectables::inject_fields;
+
+    // `id` is injected as `pub(super)`, visible in `a` only
+    #[inject_fields(super::Parent)]
+    #[derive(Default)]
+    pub struct Child {
+      pub name: String,
+    }
+  }
+}
+
+fn main() {
+  let child = a::b::Child::default();
+  // should fail - `id` is not visible from the crate root
+  println!("{}", child.id);
+}
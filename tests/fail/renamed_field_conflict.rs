@@ -0,0 +1,18 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+pub struct Named {
+  pub name: String,
+}
+
+#[inject_fields(Named, Base(rename(id = name)))]  // should fail - 'name' would be both String and u64
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
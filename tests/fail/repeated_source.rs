@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Range<T> {
+  pub start: T,
+  pub end: T,
+}
+
+#[inject_fields(Range<u32>(prefix = "x_"), Range<u32>(prefix = "x_"))]  // should fail - the same injection twice
+pub struct Rect {
+  pub label: String,
+}
+
+fn main() {}
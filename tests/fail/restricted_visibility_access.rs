@@ -0,0 +1,19 @@
+mod outer {
+  pub mod inner {
+    use injectables::injectable;
+
+    #[injectable]
+    pub struct Account {
+      pub(super) id: u64,  // visible from `outer` only
+      pub owner: String,
+    }
+  }
+}
+
+// should fail - `id` is not visible from the crate root
+#[injectables::inject_fields(outer::inner::Account)]
+pub struct Audit {
+  pub reviewed: bool,
+}
+
+fn main() {}
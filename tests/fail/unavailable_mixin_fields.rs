@@ -0,0 +1,63 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(methods)]
+pub struct Auditable {
+  pub created_at: u64,
+  pub updated_at: u64,
+}
+
+#[injectable]
+impl Auditable {
+  pub fn touch(&mut self, now: u64) {
+    self.updated_at = now;
+  }
+
+  pub fn age(&self, now: u64) -> u64 {
+    now - self.created_at
+  }
+}
+
+#[injectable(methods)]
+pub struct Versioned {
+  pub version: u32,
+}
+
+#[injectable]
+impl Versioned {
+  pub fn touch(&mut self) {
+    self.version += 1;
+  }
+}
+
+// should fail - touch uses updated_at, which is left out
+#[inject_fields(Auditable(except(updated_at)))]
+pub struct Document {
+  pub title: String,
+}
+
+// should fail - the overriding field replaces the one age uses
+#[inject_fields(Auditable)]
+pub struct Report {
+  #[inject(override)]
+  pub created_at: String,
+}
+
+// should fail - both sources mix in touch
+#[inject_fields(Auditable, Versioned)]
+pub struct Page {
+  pub body: String,
+}
+
+// should fail - there is no method named tuoch
+#[inject_fields(Auditable(except(tuoch)))]
+pub struct Note {
+  pub text: String,
+}
+
+// should fail - enums keep their fields in their variants
+#[inject_fields(Versioned)]
+pub enum Event {
+  Created,
+}
+
+fn main() {}
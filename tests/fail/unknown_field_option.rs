@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[inject_fields(Base, builder)]
+pub struct Document {
+  #[inject(fallback = "Untitled")]  // should fail - fallback is not a field option
+  pub title: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use injectables::injectable;
+
+#[injectable(exported)]
+pub struct Base {
+  pub id: u64,
+}
+
+fn main() {}
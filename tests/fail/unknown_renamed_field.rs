@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[inject_fields(Base(rename(identifier = base_id)))]  // should fail - Base has no field 'identifier'
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Auditable {
+  pub created_at: u64,
+  pub created_by: String,
+}
+
+#[inject_fields(Auditable(except(updated_at)))]  // should fail - Auditable has no field 'updated_at'
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
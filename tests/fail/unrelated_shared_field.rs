@@ -0,0 +1,18 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+pub struct Ticket {
+  pub id: u64,
+}
+
+#[inject_fields(Base, Ticket)]  // should fail - Base and Ticket declare separate fields named id
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
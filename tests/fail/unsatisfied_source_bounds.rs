@@ -0,0 +1,26 @@
+use std::fmt::Display;
+
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Container<T: Display> {
+  pub data: T,
+}
+
+pub struct Opaque;
+
+#[inject_fields(Container<Opaque>)]  // should fail - Opaque does not implement Display
+pub struct Wrapper {
+  pub label: String,
+}
+
+#[inject_fields(Container<T>)]  // should fail - T is not bounded by Display at the use site
+pub struct Forwarding<T> {
+  pub label: String,
+}
+
+impl<T> Forwarding<T> {
+  fn label(&self) -> &str { &self.label }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[inject_fields(Base)]
+pub struct Document {
+  #[inject(override)]  // should fail - no source injects a field named title
+  pub title: String,
+}
+
+fn main() {}
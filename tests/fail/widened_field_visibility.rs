@@ -0,0 +1,9 @@
+use injectables::injectable;
+
+#[injectable]
+pub struct Secret {
+  #[inject(vis = pub)]  // should fail - `key` is private
+  key: u64,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Auditable {
+  pub created_by: String,
+  pub(crate) updated_by: String,
+  reviewed_by: String,
+}
+
+// should fail - `updated_by` and `reviewed_by` are not pub in Auditable
+#[inject_fields(Auditable(vis = pub))]
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {}
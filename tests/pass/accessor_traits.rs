@@ -0,0 +1,75 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(accessors)]
+pub struct Auditable {
+  pub created_by: String,
+  pub version: u32,
+}
+
+#[injectable(accessors)]
+pub struct Container<T> {
+  pub data: T,
+}
+
+#[inject_fields(Auditable)]
+pub struct Document {
+  pub title: String,
+}
+
+// Passes HasAuditable on to the structs injecting it
+#[injectable]
+#[inject_fields(Auditable)]
+pub struct Record {
+  pub id: u64,
+}
+
+#[inject_fields(Record(prefix = "record_"), Container<String>)]
+pub struct Page {
+  pub number: u32,
+}
+
+#[inject_fields(Auditable(embed))]
+pub struct Note {
+  pub text: String,
+}
+
+fn audit(item: &mut impl HasAuditable) -> String {
+  *item.version_mut() += 1;
+  format!("{} v{}", item.created_by(), item.version())
+}
+
+fn main() {
+  let mut auditable = Auditable {
+    created_by: "root".to_string(),
+    version: 0,
+  };
+  assert_eq!(audit(&mut auditable), "root v1");
+
+  let mut document = Document {
+    title: "Report".to_string(),
+    created_by: "admin".to_string(),
+    version: 1,
+  };
+  assert_eq!(audit(&mut document), "admin v2");
+  assert_eq!(document.version, 2);
+
+  let mut page = Page {
+    number: 3,
+    record_id: 4,
+    record_created_by: "editor".to_string(),
+    record_version: 5,
+    data: "text".to_string(),
+  };
+  assert_eq!(audit(&mut page), "editor v6");
+  page.data_mut().push('!');
+  assert_eq!(HasContainer::<String>::data(&page), "text!");
+
+  let mut note = Note {
+    text: "Hello".to_string(),
+    auditable: Auditable {
+      created_by: "guest".to_string(),
+      version: 7,
+    },
+  };
+  assert_eq!(audit(&mut note), "guest v8");
+}
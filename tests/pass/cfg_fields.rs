@@ -0,0 +1,64 @@
+use injectables::{inject_fields, injectable};
+
+// Fields configured out leave no trace in what is generated for them
+#[injectable(accessors)]
+pub struct Tracked {
+  pub id: u64,
+  #[cfg(any())]
+  pub trace: Vec<String>,
+}
+
+#[injectable(views)]
+pub struct Stamped {
+  pub at: u64,
+  #[cfg(any())]
+  pub zone: String,
+}
+
+#[injectable]
+#[inject_fields(Tracked, Stamped)]
+pub struct Order {
+  pub total: u32,
+}
+
+#[inject_fields(Order, builder)]
+pub struct Invoice {
+  pub number: u32,
+  #[cfg(any())]
+  pub draft: bool,
+}
+
+fn describe(item: &mut impl HasTracked) -> u64 {
+  *item.id_mut() += 1;
+  *item.id()
+}
+
+fn main() {
+  let mut tracked = Tracked { id: 1 };
+  assert_eq!(describe(&mut tracked), 2);
+
+  let mut stamped = Stamped { at: 1 };
+  *stamped.as_stamped_mut().at += 1;
+  assert_eq!(*stamped.as_stamped_ref().at, 2);
+
+  let mut order = Order {
+    total: 3,
+    id: 4,
+    at: 5,
+  };
+  assert_eq!(describe(&mut order), 5);
+  *order.as_stamped_mut().at += 1;
+  assert_eq!(*order.as_stamped_ref().at, 6);
+
+  let mut invoice = Invoice {
+    number: 6,
+    total: 7,
+    id: 8,
+    at: 9,
+  };
+  assert_eq!(describe(&mut invoice), 9);
+  assert_eq!(*invoice.as_stamped_ref().at, 9);
+
+  let built = Invoice::builder().number(1).total(2).id(3).at(4).build().unwrap();
+  assert_eq!((built.number, built.total, built.id, built.at), (1, 2, 3, 4));
+}
@@ -0,0 +1,70 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[injectable]
+#[derive(Debug, PartialEq)]
+pub struct Auditable {
+  pub created_by: String,
+}
+
+#[injectable]
+#[derive(Debug, PartialEq)]
+pub struct Container<T> {
+  pub data: T,
+}
+
+#[injectable]
+pub struct Lock {
+  pub count: std::sync::Mutex<u32>,
+}
+
+#[inject_fields(Base(rename(id = base_id)), Auditable, conversions)]
+pub struct Document {
+  pub title: String,
+}
+
+#[inject_fields(Container<T>, Base(embed), conversions)]
+pub struct Wrapper<T> {
+  pub label: &'static str,
+}
+
+// `to_lock` cannot clone the mutex, which only matters if it is called
+#[inject_fields(Lock, conversions)]
+pub struct Counter {
+  pub name: String,
+}
+
+fn main() {
+  let document = Document::from_parts(
+    Base { id: 1, version: 2 },
+    Auditable { created_by: "admin".to_string() },
+    "Report".to_string(),
+  );
+  assert_eq!(document.base_id, 1);
+  assert_eq!(document.to_base(), Base { id: 1, version: 2 });
+  assert_eq!(document.to_auditable().created_by, "admin");
+
+  let (base, auditable, title) = document.into_parts();
+  assert_eq!(base, Base { id: 1, version: 2 });
+  assert_eq!(auditable.created_by, "admin");
+  assert_eq!(title, "Report");
+
+  let document = Document::from_parts(base, auditable, title);
+  assert_eq!(Auditable::from(document), Auditable { created_by: "admin".to_string() });
+
+  let wrapper = Wrapper::from_parts(Container { data: 7u8 }, Base { id: 3, version: 1 }, "seven");
+  assert_eq!(wrapper.to_base(), Base { id: 3, version: 1 });
+  assert_eq!(wrapper.version, 1);
+  assert_eq!(Container::from(wrapper), Container { data: 7u8 });
+
+  let counter = Counter::from_parts(Lock { count: std::sync::Mutex::new(1) }, "hits".to_string());
+  let (lock, name) = counter.into_parts();
+  assert_eq!(*lock.count.lock().unwrap(), 1);
+  assert_eq!(name, "hits");
+}
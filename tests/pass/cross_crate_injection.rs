@@ -0,0 +1,67 @@
+use injectables::inject_fields;
+use injectables_test_models::{audit::HasAuditable, ids::UserId};
+
+#[inject_fields(injectables_test_models::audit::Auditable)]
+pub struct Comment {
+  pub body: String,
+}
+
+#[inject_fields(injectables_test_models::audit::Tagged<String>)]
+pub struct Post {
+  pub title: String,
+}
+
+#[inject_fields(injectables_test_models::audit::Window<'a, 2>)]
+pub struct Feed<'a> {
+  pub name: &'a str,
+}
+
+#[inject_fields(injectables_test_models::audit::Signature)]
+pub struct Signed(pub bool);
+
+// Transitive: Record injects Auditable within the exporting crate
+#[inject_fields(injectables_test_models::records::Record)]
+pub struct Entry {
+  pub note: String,
+}
+
+fn main() {
+  let comment = Comment {
+    body: "Hello".to_string(),
+    created_by: UserId(7),
+    created_at: 1,
+  };
+  assert_eq!(comment.created_by, UserId(7));
+  assert_eq!(*comment.created_at(), 1);
+
+  let post = Post {
+    title: "Title".to_string(),
+    tags: vec!["rust".to_string()],
+    owner: Some(UserId(1)),
+  };
+  assert_eq!(post.tags.len(), 1);
+  assert_eq!(post.owner, Some(UserId(1)));
+  // methods of an exported source come along, naming its crate's items through `crate::`
+  assert!(post.is_owned_by(UserId(1)));
+  assert_eq!(post.tag_count(), 1);
+
+  let signed = Signed(true, UserId(3), 4);
+  assert_eq!((signed.1, signed.2), (UserId(3), 4));
+
+  let viewer = UserId(5);
+  let feed = Feed {
+    name: "feed",
+    viewer: &viewer,
+    recent: [UserId(1), UserId(2)],
+  };
+  assert_eq!(feed.viewer.0 + feed.recent[1].0, 7);
+
+  let entry = Entry {
+    note: "Note".to_string(),
+    revision: 2,
+    created_by: UserId(3),
+    created_at: 4,
+  };
+  assert_eq!(entry.revision + entry.created_by.0 as u32, 5);
+  assert_eq!(entry.created_by(), &UserId(3));
+}
@@ -0,0 +1,49 @@
+use injectables::{injectable, inject_fields};
+
+// Targets are declared before the structs they inject from
+#[inject_fields(B)]
+pub struct C {
+  pub description: String,
+}
+
+#[inject_fields(models::Base)]
+pub struct Document {
+  pub title: String,
+}
+
+// `#[inject_fields]` may also come before `#[injectable]`
+#[inject_fields(A)]
+#[injectable]
+pub struct B {
+  pub name: String,
+}
+
+#[injectable]
+pub struct A {
+  pub id: u64,
+}
+
+mod models {
+  use injectables::injectable;
+
+  #[injectable]
+  pub struct Base {
+    pub created_at: String,
+  }
+}
+
+fn main() {
+  let c = C {
+    description: "Test".to_string(),
+    name: "Test".to_string(),
+    id: 1,
+  };
+  let doc = Document {
+    title: "Test".to_string(),
+    created_at: "2024-01-01".to_string(),
+  };
+
+  assert_eq!(c.id, 1);
+  assert_eq!(c.name, "Test");
+  assert_eq!(doc.created_at, "2024-01-01");
+}
@@ -0,0 +1,62 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Base {
+  pub id: u64,
+  pub tag: String,
+}
+
+#[injectable]
+#[inject_fields(Base)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Named {
+  pub name: String,
+}
+
+#[injectable]
+#[inject_fields(Base)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Versioned {
+  pub version: u32,
+}
+
+// The fields of Base arrive through both sources; both still convert, sharing them
+#[inject_fields(Named, Versioned, conversions)]
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {
+  let named = Named {
+    name: "Name".to_string(),
+    id: 1,
+    tag: "draft".to_string(),
+  };
+  let versioned = Versioned {
+    version: 2,
+    id: 3,
+    tag: "ignored".to_string(),
+  };
+
+  // Shared fields are taken from the first source
+  let document = Document::from_parts(named.clone(), versioned, "Title".to_string());
+  assert_eq!(document.id, 1);
+  assert_eq!(document.tag, "draft");
+
+  let expected = Versioned {
+    version: 2,
+    id: 1,
+    tag: "draft".to_string(),
+  };
+  assert_eq!(document.to_versioned(), expected);
+  assert_eq!(document.to_named(), named);
+
+  let (split_named, split_versioned, title) = document.into_parts();
+  assert_eq!(split_named, named);
+  assert_eq!(split_versioned, expected);
+  assert_eq!(title, "Title");
+
+  let document = Document::from_parts(split_named, split_versioned, title);
+  assert_eq!(Versioned::from(document), expected);
+}
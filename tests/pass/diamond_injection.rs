@@ -0,0 +1,55 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+pub struct Tagged<T> {
+  pub tag: T,
+}
+
+#[injectable]
+#[inject_fields(Base, Tagged<String>)]
+pub struct Named {
+  pub name: String,
+}
+
+#[injectable]
+#[inject_fields(Base, Tagged<String>)]
+pub struct Versioned {
+  pub version: u32,
+}
+
+// Base and Tagged<String> arrive through both Named and Versioned, and are injected once
+#[inject_fields(Named, Versioned)]
+pub struct Document {
+  pub title: String,
+}
+
+// A shared ancestor may also be named directly next to the structs carrying it
+#[inject_fields(Named, Versioned, Base)]
+pub struct Comment {
+  pub body: String,
+}
+
+fn main() {
+  let document = Document {
+    title: "Title".to_string(),
+    name: "Name".to_string(),
+    id: 1,
+    tag: "draft".to_string(),
+    version: 2,
+  };
+  let comment = Comment {
+    body: "Body".to_string(),
+    name: "Name".to_string(),
+    id: 3,
+    tag: "reply".to_string(),
+    version: 1,
+  };
+
+  assert_eq!(document.id + comment.id, 4);
+  assert_eq!(document.tag, "draft");
+}
@@ -0,0 +1,55 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[injectable]
+pub struct Auditable {
+  pub created_by: String,
+}
+
+#[injectable]
+pub struct Container<T> {
+  pub data: T,
+}
+
+#[inject_fields(Base(embed), Auditable)]
+pub struct Document {
+  pub title: String,
+}
+
+#[inject_fields(Base(embed = meta), Container<String>(embed))]
+pub struct Attachment {
+  pub name: String,
+}
+
+fn touch(base: &mut Base) {
+  base.version += 1;
+}
+
+fn main() {
+  let mut document = Document {
+    title: "Report".to_string(),
+    base: Base { id: 1, version: 1 },
+    created_by: "admin".to_string(),
+  };
+
+  // Fields of the embedded source are reached through `Deref`
+  assert_eq!(document.id, 1);
+  document.version = 2;
+  touch(&mut document);
+  assert_eq!(document.base, Base { id: 1, version: 3 });
+  assert_eq!(document.created_by, "admin");
+
+  let attachment = Attachment {
+    name: "report.pdf".to_string(),
+    meta: document.base.clone(),
+    container: Container { data: "%PDF".to_string() },
+  };
+  assert_eq!(attachment.meta.id, 1);
+  assert_eq!(attachment.container.data, "%PDF");
+}
@@ -0,0 +1,84 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Envelope {
+  pub id: u64,
+  pub sent_at: String,
+}
+
+#[injectable]
+pub struct Trace<T> {
+  pub trace: Option<T>,
+}
+
+// every struct-like variant gets the fields, unit variants included
+#[inject_fields(Envelope)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+  Created { name: String },
+  Renamed { from: String, to: String },
+  Deleted,
+}
+
+// a skipped variant is left as it is, and accessors return an Option
+#[inject_fields(Envelope, Trace<T>(except(trace)), Trace<u32>(rename(trace = code)))]
+pub enum Message<T> {
+  Request { body: T },
+  Response {
+    #[inject(override)]
+    sent_at: u64,
+  },
+  #[inject(skip)]
+  Ping(u8),
+  #[inject(skip)]
+  Close,
+}
+
+fn main() {
+  let mut event = Event::Created {
+    name: "report".to_string(),
+    id: 1,
+    sent_at: "2024-01-01".to_string(),
+  };
+  assert_eq!(*event.id(), 1);
+  *event.sent_at_mut() = "2024-01-02".to_string();
+  assert_eq!(event.sent_at(), "2024-01-02");
+
+  let renamed = Event::Renamed {
+    from: "a".to_string(),
+    to: "b".to_string(),
+    id: 2,
+    sent_at: String::new(),
+  };
+  assert_eq!(*renamed.id(), 2);
+
+  let deleted = Event::Deleted {
+    id: 3,
+    sent_at: String::new(),
+  };
+  assert_eq!(*deleted.id(), 3);
+
+  let mut request = Message::Request {
+    body: "payload",
+    id: 4,
+    sent_at: "2024-01-01".to_string(),
+    code: Some(200),
+  };
+  assert_eq!(request.id(), Some(&4));
+  if let Some(code) = request.code_mut() {
+    *code = Some(404);
+  }
+  assert_eq!(request.code(), Some(&Some(404)));
+  // the overriding variant keeps its own field, so only the request has the injected one
+  assert_eq!(request.sent_at().map(String::as_str), Some("2024-01-01"));
+
+  let response = Message::<&str>::Response {
+    sent_at: 5,
+    id: 5,
+    code: None,
+  };
+  assert_eq!(response.sent_at(), None);
+  assert_eq!(response.id(), Some(&5));
+  assert_eq!(Message::<&str>::Ping(0).id(), None);
+  assert_eq!(Message::<&str>::Close.code(), None);
+}
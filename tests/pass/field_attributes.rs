@@ -0,0 +1,56 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  /// Unique identifier
+  pub id: u64,
+  #[cfg(any())]
+  pub disabled: u64,
+}
+
+// The `cfg` attribute is carried over, so `disabled` is compiled out of the target as well
+#[inject_fields(Base)]
+#[derive(Debug, Default, PartialEq)]
+pub struct Document {
+  pub title: String,
+}
+
+#[inject_fields(Base, strip_attrs(cfg))]
+pub struct Stripped {
+  pub title: String,
+}
+
+#[inject_fields(Base, keep_attrs(doc))]
+pub struct Kept {
+  pub title: String,
+}
+
+#[inject_fields(Base, strip_attrs)]
+pub struct Bare {
+  pub title: String,
+}
+
+fn main() {
+  let doc = Document {
+    title: "Test".to_string(),
+    id: 1,
+  };
+  assert_eq!(doc, Document { title: "Test".to_string(), id: 1 });
+
+  let stripped = Stripped {
+    title: "Test".to_string(),
+    id: 1,
+    disabled: 2,
+  };
+  let kept = Kept {
+    title: "Test".to_string(),
+    id: 1,
+    disabled: 2,
+  };
+  let bare = Bare {
+    title: "Test".to_string(),
+    id: 1,
+    disabled: 2,
+  };
+  assert_eq!(stripped.disabled + kept.disabled + bare.disabled, 6);
+}
@@ -0,0 +1,53 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(accessors)]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[injectable(views)]
+pub struct Auditable {
+  pub created_by: String,
+}
+
+// Overrides may change the type and the visibility of the injected field
+#[inject_fields(Base, Auditable, builder)]
+pub struct Document {
+  #[inject(override)]
+  id: String,
+  #[inject(override, default = "system".to_string())]
+  pub created_by: String,
+}
+
+impl Document {
+  fn id(&self) -> &str {
+    &self.id
+  }
+}
+
+#[injectable]
+#[inject_fields(Base)]
+pub struct Versioned {
+  #[inject(override)]
+  pub version: u64,
+}
+
+#[inject_fields(Versioned)]
+pub struct Release {
+  pub name: String,
+}
+
+fn main() {
+  let document = Document::builder().id("doc-1").version(2).build().unwrap();
+  assert_eq!(document.id(), "doc-1");
+  assert_eq!(document.version, 2);
+  assert_eq!(document.created_by, "system");
+
+  let release = Release {
+    name: "1.0".to_string(),
+    id: 1,
+    version: u64::MAX,
+  };
+  assert_eq!(release.version, u64::MAX);
+}
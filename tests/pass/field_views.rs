@@ -0,0 +1,66 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(views)]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[injectable(views)]
+pub struct Container<T> {
+  pub data: T,
+}
+
+#[inject_fields(Base, Container<String>)]
+pub struct Document {
+  pub title: String,
+}
+
+#[injectable]
+#[inject_fields(Base(rename(id = base_id)))]
+pub struct Record {
+  pub revision: u32,
+}
+
+#[inject_fields(Record(embed))]
+pub struct Page {
+  pub number: u32,
+}
+
+fn describe(base: BaseRef<'_>) -> String {
+  format!("{}@{}", base.id, base.version)
+}
+
+fn bump(base: BaseMut<'_>) {
+  *base.version += 1;
+}
+
+fn main() {
+  let mut base = Base { id: 1, version: 1 };
+  bump(base.as_base_mut());
+  assert_eq!(describe(base.as_base_ref()), "1@2");
+
+  let mut document = Document {
+    title: "Report".to_string(),
+    id: 2,
+    version: 1,
+    data: "text".to_string(),
+  };
+  bump(document.as_base_mut());
+  assert_eq!(describe(document.as_base_ref()), "2@2");
+  document.as_container_mut().data.push('!');
+  let container: ContainerRef<'_, String> = document.as_container_ref();
+  assert_eq!(container.data, "text!");
+
+  let mut page = Page {
+    number: 3,
+    record: Record {
+      revision: 1,
+      base_id: 4,
+      version: 5,
+    },
+  };
+  bump(page.as_base_mut());
+  assert_eq!(describe(page.as_base_ref()), "4@6");
+  assert_eq!(page.record.version, 6);
+}
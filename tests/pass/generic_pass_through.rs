@@ -0,0 +1,74 @@
+use std::fmt::Display;
+
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Container<T: Display>
+where
+  T: Clone,
+{
+  pub data: T,
+}
+
+#[injectable]
+pub struct Pair<A, B = String> {
+  pub first: A,
+  pub second: B,
+}
+
+#[injectable]
+pub struct Borrowed<'a, T: 'a> {
+  pub item: &'a T,
+}
+
+// `T: Display + Clone` is merged into the target from the source's bounds
+#[inject_fields(Container<T>)]
+pub struct Wrapper<T: Default> {
+  pub label: String,
+}
+
+impl<T: Default + Display + Clone> Wrapper<T> {
+  fn describe(&self) -> String { format!("{}: {}", self.label, self.data.clone()) }
+}
+
+#[inject_fields(Pair<U, T>)]
+pub struct Swapped<T, U> {
+  pub count: usize,
+}
+
+#[inject_fields(Pair<u8>)]
+pub struct Defaulted {
+  pub count: usize,
+}
+
+#[inject_fields(Container<Vec<T>>, Borrowed<'a, T>)]
+pub struct Nested<'a, T>
+where
+  Vec<T>: Display,
+{
+  pub count: usize,
+}
+
+fn main() {
+  let wrapper = Wrapper {
+    label: "answer".to_string(),
+    data: 42,
+  };
+  assert_eq!(wrapper.describe(), "answer: 42");
+
+  let swapped = Swapped {
+    count: 1,
+    first: "first",
+    second: 2u8,
+  };
+  assert_eq!(swapped.first, "first");
+  assert_eq!(swapped.second, 2);
+
+  let defaulted = Defaulted {
+    count: 2,
+    first: 3,
+    second: "second".to_string(),
+  };
+  assert_eq!(defaulted.second, "second");
+  assert_eq!(swapped.count + defaulted.count + defaulted.first as usize, 6);
+}
@@ -0,0 +1,60 @@
+use injectables::{inject_fields, injectable};
+
+pub struct Timestamp(pub u64);
+pub struct Tx;
+pub struct TT;
+
+pub trait Codec {
+  type Output;
+}
+
+impl Codec for u8 {
+  type Output = String;
+}
+
+impl Codec for Vec<u8> {
+  type Output = Vec<String>;
+}
+
+#[injectable]
+pub struct Event<T: Codec, U>
+where
+  U: Codec + Clone,
+{
+  pub at: Timestamp,
+  pub tx: Tx,
+  pub both: (TT, T),
+  pub payload: Option<T>,
+  pub decoded: <T as Codec>::Output,
+  pub encoded: U::Output,
+  pub handler: Box<dyn Fn(&T) -> Tx>,
+  pub convert: fn(T) -> Option<U>,
+  pub items: Vec<(T, [U; 2])>,
+}
+
+#[inject_fields(Event<u8, Vec<u8>>)]
+pub struct Message {
+  pub topic: String,
+}
+
+fn main() {
+  let message = Message {
+    topic: "events".to_string(),
+    at: Timestamp(1),
+    tx: Tx,
+    both: (TT, 2u8),
+    payload: Some(3u8),
+    decoded: "decoded".to_string(),
+    encoded: vec!["encoded".to_string()],
+    handler: Box::new(|_: &u8| Tx),
+    convert: |value: u8| Some(vec![value]),
+    items: vec![(4u8, [vec![5u8], vec![6u8]])],
+  };
+
+  assert_eq!(message.at.0, 1);
+  assert_eq!(message.payload, Some(3));
+  assert_eq!(message.decoded, "decoded");
+  assert_eq!(message.encoded.len(), 1);
+  assert_eq!((message.convert)(7), Some(vec![7]));
+  let _ = ((message.handler)(&8), message.tx, message.both, message.items, message.topic);
+}
@@ -0,0 +1,91 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Slice<'a> {
+  pub data: &'a [u8],
+}
+
+#[injectable]
+pub struct Buf<const N: usize> {
+  pub bytes: [u8; N],
+  pub halves: Halves<N>,
+}
+
+pub struct Halves<const N: usize>(pub [u8; N]);
+
+#[injectable]
+pub struct Frame<'a, T, const N: usize> {
+  pub header: &'a T,
+  pub slots: [Option<T>; N],
+}
+
+const SIZE: usize = 4;
+
+#[inject_fields(Slice<'a>)]
+pub struct View<'a> {
+  pub name: &'a str,
+}
+
+#[inject_fields(Slice<'static>)]
+pub struct StaticView {
+  pub name: String,
+}
+
+#[inject_fields(Buf<16>)]
+pub struct Packet {
+  pub id: u32,
+}
+
+#[inject_fields(Buf<SIZE>)]
+pub struct NamedPacket {
+  pub id: u32,
+}
+
+#[inject_fields(Buf<{ SIZE * 2 }>)]
+pub struct ComputedPacket {
+  pub id: u32,
+}
+
+#[inject_fields(Frame<'a, u16, 3>)]
+pub struct Message<'a> {
+  pub topic: &'a str,
+}
+
+fn main() {
+  let bytes = vec![1, 2, 3];
+  let view = View {
+    name: "view",
+    data: &bytes,
+  };
+  let static_view = StaticView {
+    name: "static".to_string(),
+    data: b"static",
+  };
+  assert_eq!(view.data.len() + static_view.data.len(), 9);
+
+  let packet = Packet {
+    id: 1,
+    bytes: [0; 16],
+    halves: Halves([0; 16]),
+  };
+  let named = NamedPacket {
+    id: 2,
+    bytes: [0; SIZE],
+    halves: Halves([0; SIZE]),
+  };
+  let computed = ComputedPacket {
+    id: 3,
+    bytes: [0; 8],
+    halves: Halves([0; 8]),
+  };
+  assert_eq!(packet.bytes.len() + named.halves.0.len() + computed.bytes.len(), 28);
+
+  let header = 7u16;
+  let message = Message {
+    topic: "frames",
+    header: &header,
+    slots: [Some(1), None, Some(3)],
+  };
+  assert_eq!(*message.header, 7);
+  assert_eq!(message.slots.len(), 3);
+}
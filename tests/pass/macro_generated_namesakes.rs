@@ -0,0 +1,41 @@
+use injectables::inject_fields;
+
+// Every invocation defines a `Record` at the same span, inside the macro's own body
+macro_rules! records {
+  ($module:ident, $value:ty) => {
+    pub mod $module {
+      use injectables::injectable;
+
+      #[injectable(export)]
+      pub struct Record {
+        pub value: $value,
+      }
+    }
+  };
+}
+
+records!(counts, u32);
+records!(labels, String);
+
+#[inject_fields(counts::Record)]
+pub struct Count {
+  pub name: String,
+}
+
+#[inject_fields(labels::Record)]
+pub struct Label {
+  pub name: String,
+}
+
+fn main() {
+  let count = Count {
+    name: "count".to_string(),
+    value: 3,
+  };
+  let label = Label {
+    name: "label".to_string(),
+    value: "three".to_string(),
+  };
+  assert_eq!(count.value, 3);
+  assert_eq!(label.value, "three");
+}
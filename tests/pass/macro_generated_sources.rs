@@ -0,0 +1,38 @@
+use injectables::{inject_fields, injectable};
+
+pub mod ids {
+  #[derive(Clone, Copy, Debug, Default, PartialEq)]
+  pub struct Id(pub u64);
+}
+
+pub use ids::Id;
+
+// Field types written by a macro may name items through `$crate`
+macro_rules! identified {
+  ($name:ident) => {
+    #[injectable]
+    pub struct $name<T> {
+      pub id: $crate::Id,
+      pub data: Option<T>,
+    }
+  };
+}
+
+identified!(Identified);
+
+#[inject_fields(Identified<String>)]
+#[derive(Default)]
+pub struct User {
+  pub name: String,
+}
+
+fn main() {
+  let user = User {
+    name: "user".to_string(),
+    id: Id(1),
+    data: Some("data".to_string()),
+  };
+  assert_eq!(user.id, Id(1));
+  assert_eq!(user.data.as_deref(), Some("data"));
+  assert_eq!(User::default().id, Id(0));
+}
@@ -0,0 +1,152 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(methods)]
+pub struct Auditable {
+  pub created_at: u64,
+  pub updated_at: u64,
+  revision: u32,
+}
+
+#[injectable]
+impl Auditable {
+  pub const EPOCH: u64 = 0;
+
+  /// Marks the struct as updated at `now`.
+  pub fn touch(&mut self, now: u64) {
+    self.updated_at = now;
+    self.revision += 1;
+  }
+
+  pub fn age(&self, now: u64) -> u64 {
+    now - self.created_at
+  }
+
+  pub fn describe(&self) -> String {
+    format!("revision {} since {}", self.revision, self.created_at)
+  }
+
+  pub fn touched(mut self, now: u64) -> Self {
+    self.touch(now);
+    self
+  }
+}
+
+#[injectable(methods)]
+pub struct Tagged<T: Clone> {
+  pub tags: Vec<T>,
+}
+
+#[injectable]
+impl<T: Clone> Tagged<T> {
+  pub fn tag(&mut self, tag: T) -> &mut Self {
+    self.tags.push(tag);
+    self
+  }
+
+  pub fn first_tag(&self) -> Option<T> {
+    self.tags.first().cloned()
+  }
+}
+
+#[injectable(methods)]
+pub struct Point(pub i32, pub i32);
+
+#[injectable]
+impl Point {
+  pub fn sum(&self) -> i32 {
+    self.0 + self.1
+  }
+}
+
+mod clock {
+  use injectables::injectable;
+
+  #[injectable(methods)]
+  pub struct Clock {
+    pub(crate) ticks: u64,
+  }
+
+  #[injectable]
+  impl Clock {
+    pub(crate) fn tick(&mut self) -> u64 {
+      self.ticks += 1;
+      self.ticks
+    }
+  }
+}
+
+use clock::Clock;
+
+// methods follow the fields into the target, renamed with them
+#[inject_fields(Auditable(rename(updated_at = modified_at)), Tagged<String>)]
+pub struct Document {
+  pub title: String,
+}
+
+// `except` leaves out methods as well as fields, and generic targets forward their parameters
+#[inject_fields(Auditable(except(updated_at, touch, touched)), Tagged<T>)]
+pub struct Snapshot<T: Clone> {
+  pub data: T,
+}
+
+// embedded sources are reached through their field
+#[inject_fields(Auditable(embed = audit))]
+pub struct Entry {
+  pub name: String,
+}
+
+// a source imported with `use` brings its methods along
+#[inject_fields(Clock)]
+pub struct Timer {
+  pub label: &'static str,
+}
+
+#[inject_fields(Point(prepend))]
+pub struct Labeled(pub &'static str);
+
+fn main() {
+  let mut document = Document {
+    title: "Title".to_string(),
+    created_at: 10,
+    modified_at: 10,
+    revision: 0,
+    tags: Vec::new(),
+  };
+  document.touch(20);
+  assert_eq!(document.modified_at, 20);
+  assert_eq!(document.age(25), 15);
+  assert_eq!(document.describe(), "revision 1 since 10");
+  document.tag("draft".to_string()).tag("new".to_string());
+  assert_eq!(document.first_tag().as_deref(), Some("draft"));
+  assert_eq!(Document::EPOCH, 0);
+  let document = document.touched(30);
+  assert_eq!(document.modified_at, 30);
+  assert_eq!(document.title, "Title");
+
+  let snapshot = Snapshot {
+    data: 1u8,
+    created_at: 5,
+    revision: 2,
+    tags: vec![7u8],
+  };
+  assert_eq!(snapshot.age(6), 1);
+  assert_eq!(snapshot.first_tag(), Some(7));
+
+  let mut entry = Entry {
+    name: "entry".to_string(),
+    audit: Auditable {
+      created_at: 1,
+      updated_at: 1,
+      revision: 0,
+    },
+  };
+  entry.touch(3);
+  assert_eq!(entry.audit.updated_at, 3);
+  assert_eq!(entry.describe(), "revision 1 since 1");
+
+  let mut timer = Timer { label: "timer", ticks: 0 };
+  timer.tick();
+  assert_eq!(timer.tick(), 2);
+
+  assert_eq!(Labeled(1, 2, "label").sum(), 3);
+}
@@ -0,0 +1,58 @@
+use injectables::inject_fields;
+
+mod clock {
+  use injectables::injectable;
+
+  pub fn now() -> u64 {
+    42
+  }
+
+  #[injectable(methods)]
+  pub struct Clock<const N: usize> {
+    pub ticks: u64,
+  }
+
+  // Methods expand in the modules of their targets: they name items through `crate::` paths,
+  // the prelude and their own imports, bindings and generic parameters
+  #[injectable]
+  impl<const N: usize> Clock<N> {
+    pub const START: Option<u64> = None;
+
+    pub fn stamp(&mut self) -> u64 {
+      self.ticks = crate::clock::now();
+      self.ticks
+    }
+
+    pub fn counts(&self) -> std::collections::HashMap<u64, usize> {
+      use std::collections::HashMap;
+
+      let mut counts = HashMap::new();
+      counts.insert(self.ticks, N);
+      counts
+    }
+
+    pub fn describe<T: ToString>(&self, label: T) -> String {
+      fn pad(text: String) -> String {
+        format!("[{}]", text)
+      }
+
+      let ticks = self.ticks;
+      let show = |value: u64| value.to_string();
+      pad(format!("{} {}", label.to_string(), show(ticks)))
+    }
+  }
+}
+
+#[inject_fields(clock::Clock<3>)]
+#[derive(Default)]
+pub struct Timer {
+  pub name: String,
+}
+
+fn main() {
+  let mut timer = Timer::default();
+  assert_eq!(Timer::START, None);
+  assert_eq!(timer.stamp(), 42);
+  assert_eq!(timer.counts()[&42], 3);
+  assert_eq!(timer.describe("timer"), "[timer 42]");
+}
@@ -0,0 +1,45 @@
+mod outer {
+  use injectables::injectable;
+
+  #[injectable]
+  pub struct Account {
+    pub(super) id: u64,      // visible from the crate root and its children
+    pub(crate) owner: String,
+    balance: i64,            // visible from `outer` and its children
+  }
+
+  pub mod inner {
+    use injectables::inject_fields;
+
+    // a child module may access the private fields of its ancestors
+    #[inject_fields(super::Account)]
+    pub struct Savings {
+      pub rate: f32,
+    }
+
+    pub fn savings() -> Savings {
+      Savings {
+        rate: 0.5,
+        id: 1,
+        owner: "Alice".to_string(),
+        balance: 100,
+      }
+    }
+
+    pub fn balance(savings: &Savings) -> i64 {
+      savings.balance
+    }
+  }
+}
+
+// the crate-visible fields of `outer` are visible from the crate root
+#[injectables::inject_fields(outer::Account(only(owner)))]
+pub struct Audit {
+  pub reviewed: bool,
+}
+
+fn main() {
+  let savings = outer::inner::savings();
+  assert_eq!(savings.rate, 0.5);
+  assert_eq!(outer::inner::balance(&savings), 100);
+}
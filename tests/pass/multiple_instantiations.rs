@@ -0,0 +1,68 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable(accessors, views)]
+pub struct Range<T> {
+  pub start: T,
+  pub end: T,
+}
+
+#[injectable(accessors, views)]
+pub struct Pair<T> {
+  pub left: T,
+  pub right: T,
+}
+
+// The same instantiation twice, under different names
+#[inject_fields(Range<u32>(prefix = "x_"), Range<u32>(prefix = "y_"), builder)]
+pub struct Rect {
+  pub label: String,
+}
+
+// Different instantiations, each with accessors of its own
+#[inject_fields(Pair<i32>(prefix = "num_"), Pair<String>(prefix = "text_"))]
+pub struct Mixed {
+  pub label: String,
+}
+
+#[inject_fields(Pair<i32>(embed = numbers), Pair<String>(embed = texts))]
+pub struct Embedded {
+  pub label: String,
+}
+
+fn left_of<T>(pair: &impl HasPair<T>) -> &T {
+  pair.left()
+}
+
+fn main() {
+  let rect = Rect::builder()
+    .label("r")
+    .x_start(0)
+    .x_end(4)
+    .y_start(1)
+    .y_end(3)
+    .build()
+    .unwrap();
+  assert_eq!(rect.x_end - rect.x_start, 4);
+  assert_eq!(rect.y_end - rect.y_start, 2);
+
+  let mixed = Mixed {
+    label: "m".to_string(),
+    num_left: 1,
+    num_right: 2,
+    text_left: "a".to_string(),
+    text_right: "b".to_string(),
+  };
+  assert_eq!(*left_of::<i32>(&mixed), 1);
+  assert_eq!(left_of::<String>(&mixed), "a");
+
+  let embedded = Embedded {
+    label: "e".to_string(),
+    numbers: Pair { left: 3, right: 4 },
+    texts: Pair {
+      left: "c".to_string(),
+      right: "d".to_string(),
+    },
+  };
+  assert_eq!(*left_of::<i32>(&embedded), 3);
+  assert_eq!(embedded.texts.right, "d");
+}
@@ -0,0 +1,96 @@
+// Visibilities of injected fields are rebased from the source's module onto the target's:
+//
+// | source path        | field visibility       | target module | visible in | injected as            |
+// |--------------------|------------------------|---------------|------------|------------------------|
+// | `super::Parent`    | private                | `a::b`        | `a`        | `pub(super)`           |
+// | `super::Parent`    | `pub(self)`            | `a::b`        | `a`        | `pub(super)`           |
+// | `super::Parent`    | `pub(super)`           | `a::b`        | crate root | `pub(in super::super)` |
+// | `super::Parent`    | `pub(in self)`         | `a::b`        | `a`        | `pub(super)`           |
+// | `super::Parent`    | `pub(in crate::a)`     | `a::b`        | `a`        | `pub(in crate::a)`     |
+// | `super::Parent`    | `pub(crate)`           | `a::b`        | crate root | `pub(crate)`           |
+// | `b::Child`         | `pub(super)`           | `a`           | `a`        | private                |
+// | `b::Child`         | `pub(in super::super)` | `a`           | crate root | `pub(super)`           |
+// | `b::Child`         | `pub(in crate::a)`     | `a`           | `a`        | `pub(in crate::a)`     |
+// | `super::b::Child`  | `pub(super)`           | `a::c`        | `a`        | `pub(super)`           |
+// | `super::Child`     | `pub(super)`           | `a::b::c`     | `a`        | `pub(in super::super)` |
+// | `crate::a::Parent` | private                | `a::c`        | `a`        | `pub(in crate::a)`     |
+
+mod a {
+  use injectables::{inject_fields, injectable};
+
+  #[injectable]
+  pub struct Parent {
+    private: u8,
+    pub(self) own: u8,
+    pub(super) up: u8,
+    pub(in self) scoped: u8,
+    pub(in crate::a) absolute: u8,
+    pub(crate) krate: u8,
+  }
+
+  pub mod b {
+    use injectables::{inject_fields, injectable};
+
+    #[injectable]
+    pub struct Child {
+      pub(super) up: u16,
+      pub(in super::super) top: u16,
+      pub(in crate::a) absolute: u16,
+    }
+
+    #[inject_fields(super::Parent)]
+    #[derive(Default)]
+    pub struct FromParent {}
+
+    pub mod c {
+      use injectables::inject_fields;
+
+      #[inject_fields(super::Child)]
+      #[derive(Default)]
+      pub struct FromGrandChild {}
+    }
+  }
+
+  #[inject_fields(b::Child)]
+  #[derive(Default)]
+  pub struct FromChild {}
+
+  pub mod c {
+    use injectables::inject_fields;
+
+    #[inject_fields(super::b::Child(only(up)))]
+    #[derive(Default)]
+    pub struct FromSibling {}
+
+    #[inject_fields(crate::a::Parent(only(private)))]
+    #[derive(Default)]
+    pub struct FromAbsolute {}
+  }
+
+  // Fields visible in `a`
+  pub fn read_in_a() -> u32 {
+    let parent = b::FromParent::default();
+    let child = FromChild::default();
+    let sibling = c::FromSibling::default();
+    let grandchild = b::c::FromGrandChild::default();
+    let absolute = c::FromAbsolute::default();
+    (parent.private + parent.own + parent.scoped + parent.absolute) as u32
+      + (child.up + child.absolute) as u32
+      + sibling.up as u32
+      + grandchild.up as u32
+      + absolute.private as u32
+  }
+}
+
+// Fields visible at the crate root
+fn read_at_root() -> u32 {
+  let parent = a::b::FromParent::default();
+  let child = a::FromChild::default();
+  let grandchild = a::b::c::FromGrandChild::default();
+  (parent.up + parent.krate) as u32 + child.top as u32 + grandchild.top as u32
+}
+
+fn main() {
+  assert_eq!(a::read_in_a(), 0);
+  assert_eq!(read_at_root(), 0);
+}
@@ -0,0 +1,46 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Address {
+  pub street: String,
+  pub city: String,
+}
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+  pub version: u32,
+}
+
+#[inject_fields(Address(prefix = "billing_"), Address(prefix = "shipping_"))]
+pub struct Order {
+  pub id: u64,
+}
+
+#[inject_fields(Base(rename(id = base_id)), Address(prefix = "home_", rename(city = hometown)))]
+pub struct Person {
+  pub id: String,
+}
+
+fn main() {
+  let order = Order {
+    id: 1,
+    billing_street: "1 Main St".to_string(),
+    billing_city: "Springfield".to_string(),
+    shipping_street: "2 Side St".to_string(),
+    shipping_city: "Shelbyville".to_string(),
+  };
+  assert_eq!(order.billing_city, "Springfield");
+  assert_eq!(order.shipping_street, "2 Side St");
+
+  let person = Person {
+    id: "person".to_string(),
+    base_id: 2,
+    version: 3,
+    home_street: "3 Home St".to_string(),
+    hometown: "Capital City".to_string(),
+  };
+  assert_eq!(person.base_id + person.version as u64, 5);
+  assert_eq!(person.hometown, "Capital City");
+  assert_eq!(person.id, "person");
+}
@@ -0,0 +1,30 @@
+use injectables::inject_fields;
+
+#[path = "../support/audit.rs"]
+mod audit;
+#[path = "../support/paging.rs"]
+mod paging;
+
+#[inject_fields(audit::Meta)]
+pub struct Document {
+  pub title: String,
+}
+
+#[inject_fields(paging::Meta)]
+pub struct Listing {
+  pub query: String,
+}
+
+fn main() {
+  let doc = Document {
+    title: "Test".to_string(),
+    created_by: "admin".to_string(),
+  };
+  let listing = Listing {
+    query: "rust".to_string(),
+    page: 2,
+  };
+
+  assert_eq!(doc.created_by, "admin");
+  assert_eq!(listing.page, 2);
+}
@@ -0,0 +1,48 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Auditable {
+  pub created_at: u64,
+  pub created_by: String,
+  pub updated_at: u64,
+}
+
+#[inject_fields(Auditable(only(created_at)))]
+pub struct Event {
+  pub name: String,
+}
+
+#[inject_fields(Auditable(except(created_by), prefix = "audit_"))]
+pub struct Document {
+  pub title: String,
+}
+
+// Fields left out of one source may come from another
+#[inject_fields(Auditable(only(created_by)), Auditable(except(created_by), prefix = "last_"))]
+pub struct Comment {
+  pub body: String,
+}
+
+fn main() {
+  let event = Event {
+    name: "launch".to_string(),
+    created_at: 1,
+  };
+  assert_eq!(event.created_at, 1);
+
+  let document = Document {
+    title: "Report".to_string(),
+    audit_created_at: 2,
+    audit_updated_at: 3,
+  };
+  assert_eq!(document.audit_created_at + document.audit_updated_at, 5);
+
+  let comment = Comment {
+    body: "Nice".to_string(),
+    created_by: "admin".to_string(),
+    last_created_at: 4,
+    last_updated_at: 5,
+  };
+  assert_eq!(comment.created_by, "admin");
+  assert_eq!(comment.last_updated_at - comment.last_created_at, 1);
+}
@@ -0,0 +1,38 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+#[inject_fields(Base)]
+pub struct Named {
+  pub name: String,
+}
+
+// Injecting a source that is already injected through another source is not a cycle
+#[inject_fields(Base, Named)]
+pub struct Document {
+  pub title: String,
+}
+
+#[inject_fields(Base)]
+pub struct Comment {
+  pub body: String,
+}
+
+fn main() {
+  let doc = Document {
+    title: "Title".to_string(),
+    id: 1,
+    name: "Name".to_string(),
+  };
+  let comment = Comment {
+    body: "Body".to_string(),
+    id: 2,
+  };
+
+  assert_eq!(doc.id + comment.id, 3);
+  assert_eq!(doc.name, "Name");
+}
@@ -0,0 +1,55 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Auditable {
+  pub created_by: String,
+  #[inject(default = 0)]
+  pub created_at: u64,
+}
+
+#[injectable]
+#[inject_fields(Auditable)]
+pub struct Versioned {
+  pub version: u32,
+  #[inject(default)]
+  pub is_latest: bool,
+}
+
+#[inject_fields(Versioned, builder)]
+pub struct Document {
+  pub title: String,
+  #[inject(default = "Untitled".to_string())]
+  pub subtitle: String,
+}
+
+#[injectable]
+pub struct Container<T> {
+  pub data: T,
+}
+
+#[inject_fields(Container<T>, builder)]
+pub struct Wrapper<T> {
+  pub label: &'static str,
+}
+
+fn main() {
+  let document = Document::builder()
+    .title("Report")
+    .version(2)
+    .created_by("admin")
+    .build()
+    .unwrap();
+  assert_eq!(document.title, "Report");
+  assert_eq!(document.subtitle, "Untitled");
+  assert_eq!(document.version, 2);
+  assert!(!document.is_latest);
+  assert_eq!(document.created_by, "admin");
+  assert_eq!(document.created_at, 0);
+
+  let error = Document::builder().title("Draft").build().err().unwrap();
+  assert_eq!(error.missing, vec!["version", "created_by"]);
+  assert_eq!(error.to_string(), "missing fields for Document: version, created_by");
+
+  let wrapper = Wrapper::builder().label("answer").data(42u8).build().unwrap();
+  assert_eq!(wrapper.data, 42);
+}
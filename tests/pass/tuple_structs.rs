@@ -0,0 +1,61 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Point(pub i32, pub i32);
+
+#[injectable]
+pub struct Tagged<T>(pub T);
+
+#[injectable]
+pub struct Marker;
+
+// the fields of `Point` are appended: `Labeled(&str, i32, i32)`
+#[inject_fields(Point)]
+pub struct Labeled(pub &'static str);
+
+// `prepend` puts the fields of a source first: `Mixed(u8, bool, i32, i32)`
+#[inject_fields(Tagged<u8>(prepend), Point, Marker)]
+pub struct Mixed(pub bool);
+
+// generic tuple targets pass their parameters on: `Wrapper<T>(bool, T)`
+#[inject_fields(Tagged<T>)]
+pub struct Wrapper<T: Clone>(pub bool);
+
+// tuple targets can be injected in turn: `Point3(i32, i32, i32)` and `Space(String, i32, i32, i32)`
+#[injectable]
+#[inject_fields(Point)]
+pub struct Point3(pub i32);
+
+#[inject_fields(Point3)]
+pub struct Space(pub String);
+
+mod shapes {
+  use injectables::{inject_fields, injectable};
+
+  #[injectable]
+  pub struct Secret(u64);
+
+  // private positional fields are injected within their module
+  #[inject_fields(Secret)]
+  pub struct Holder(pub u8);
+
+  pub fn holder() -> Holder { Holder(1, 2) }
+
+  pub fn secret(holder: &Holder) -> u64 { holder.1 }
+}
+
+fn main() {
+  let labeled = Labeled("origin", 0, 0);
+  assert_eq!((labeled.0, labeled.1, labeled.2), ("origin", 0, 0));
+
+  let mixed = Mixed(7, true, 1, 2);
+  assert_eq!((mixed.0, mixed.1, mixed.2, mixed.3), (7, true, 1, 2));
+
+  let wrapper = Wrapper(false, "text".to_string());
+  assert_eq!(wrapper.1, "text");
+
+  let space = Space("space".to_string(), 3, 1, 2);
+  assert_eq!((space.1, space.2, space.3), (3, 1, 2));
+
+  assert_eq!(shapes::secret(&shapes::holder()), 2);
+}
@@ -0,0 +1,44 @@
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Base {
+  pub id: u64,
+}
+
+#[injectable]
+pub struct Marker;
+
+#[injectable]
+pub struct Timestamps {
+  pub created_at: String,
+}
+
+// a unit target becomes a struct holding only the injected fields
+#[inject_fields(Base, Timestamps, Marker)]
+#[derive(Debug, Default)]
+pub struct Record;
+
+// and can be injected in turn
+#[injectable]
+#[inject_fields(Base)]
+pub struct Identified;
+
+#[inject_fields(Identified)]
+pub struct Document {
+  pub title: String,
+}
+
+fn main() {
+  let record = Record {
+    id: 1,
+    created_at: "2024-01-01".to_string(),
+  };
+  assert_eq!(record.id, 1);
+  assert_eq!(Record::default().created_at, "");
+
+  let document = Document {
+    title: "Title".to_string(),
+    id: 2,
+  };
+  assert_eq!(document.id, 2);
+}
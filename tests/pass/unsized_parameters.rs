@@ -0,0 +1,50 @@
+use std::fmt::Debug;
+
+use injectables::{inject_fields, injectable};
+
+#[injectable]
+pub struct Boxed<T: ?Sized> {
+  pub value: Box<T>,
+}
+
+#[injectable]
+pub struct Shown<T>
+where
+  T: ?Sized + Debug,
+{
+  pub shown: Box<T>,
+}
+
+// `?Sized` only relaxes the source's own parameter, and is not merged into the targets
+#[inject_fields(Boxed<str>)]
+pub struct Name {
+  pub id: u32,
+}
+
+#[inject_fields(Boxed<T>, Shown<T>)]
+pub struct Unsized<T: ?Sized + Debug> {
+  pub id: u32,
+}
+
+#[inject_fields(Boxed<T>)]
+pub struct Owned<T> {
+  pub id: u32,
+}
+
+fn main() {
+  let name = Name {
+    id: 1,
+    value: "name".into(),
+  };
+  assert_eq!(&*name.value, "name");
+
+  let slice: Unsized<[u8]> = Unsized {
+    id: 2,
+    value: Box::new([1, 2]),
+    shown: Box::new([3]),
+  };
+  assert_eq!(format!("{:?}", slice.shown), "[3]");
+
+  let owned = Owned { id: 3, value: Box::new(4u8) };
+  assert_eq!(*owned.value + slice.value[1], 6);
+}
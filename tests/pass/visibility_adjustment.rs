@@ -0,0 +1,55 @@
+mod audit {
+  use injectables::{inject_fields, injectable};
+
+  #[injectable]
+  pub struct Auditable {
+    pub created_by: String,
+    #[inject(vis = pub(crate))]  // injected as crate-visible only
+    pub updated_by: String,
+    pub(super) reviewed_by: String,
+  }
+
+  #[injectable]
+  pub struct Secret {
+    #[inject(vis = pub(crate))]  // private here, crate-visible once injected
+    key: u64,
+  }
+
+  #[inject_fields(Secret)]
+  #[derive(Default)]
+  pub struct Vault {
+    pub label: String,
+  }
+}
+
+mod api {
+  use injectables::{inject_fields, injectable};
+
+  // every field of `Auditable` is crate-visible in `Document`
+  #[injectable]
+  #[inject_fields(crate::audit::Auditable(vis = pub(crate)))]
+  #[derive(Default)]
+  pub struct Document {
+    pub title: String,
+  }
+
+  // the field option of `updated_by` does not travel past the injection
+  #[inject_fields(Document)]
+  #[derive(Default)]
+  pub struct Page {
+    pub number: u32,
+  }
+}
+
+fn main() {
+  let document = api::Document::default();
+  assert_eq!(document.created_by, "");
+  assert_eq!(document.updated_by, "");
+  assert_eq!(document.reviewed_by, "");
+
+  let page = api::Page::default();
+  assert_eq!(page.updated_by, "");
+
+  let vault = audit::Vault::default();
+  assert_eq!(vault.key, 0);
+}
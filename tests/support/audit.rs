@@ -0,0 +1,6 @@
+use injectables::injectable;
+
+#[injectable]
+pub struct Meta {
+  pub created_by: String,
+}